@@ -0,0 +1,65 @@
+const mod = require("./index.node");
+
+describe("Async iterators", () => {
+  test("a Stream is consumed with for await", async () => {
+    const sensor = new mod.Sensor();
+    const counts = [];
+    for await (const count of sensor.countdown(3)) {
+      counts.push(count);
+    }
+    expect(counts).toEqual([3, 2, 1]);
+  });
+
+  test("an ended async iterator stays done", async () => {
+    const countdown = new mod.Sensor().countdown(1);
+    expect(countdown[Symbol.asyncIterator]()).toBe(countdown);
+    expect(await countdown.next()).toEqual({ done: false, value: 1 });
+    expect(await countdown.next()).toEqual({ done: true, value: undefined });
+    expect(await countdown.next()).toEqual({ done: true, value: undefined });
+  });
+
+  test("a Receiver is only pulled as JS awaits", async () => {
+    const sensor = new mod.Sensor();
+    const samples = sensor.samples();
+    expect(await samples.next()).toEqual({ done: false, value: 0 });
+    expect(await samples.next()).toEqual({ done: false, value: 1 });
+    expect(sensor.produced).toBeLessThanOrEqual(2);
+    await samples.return();
+  });
+
+  test("breaking out of for await stops the producer", async () => {
+    const sensor = new mod.Sensor();
+    const samples = [];
+    for await (const sample of sensor.samples()) {
+      if (sample > 2) {
+        break;
+      }
+      samples.push(sample);
+    }
+    expect(samples).toEqual([0, 1, 2]);
+    while (sensor.producing) {
+      await new Promise((resolve) => setTimeout(resolve, 10));
+    }
+    expect(sensor.produced).toBe(4);
+  });
+
+  test("return() drops a Stream a next() is waiting on", async () => {
+    const sensor = new mod.Sensor();
+    const idle = sensor.idle();
+    expect(sensor.producing).toBe(true);
+    const next = idle.next();
+    await idle.return();
+    expect(await next).toEqual({ done: true, value: undefined });
+    expect(sensor.producing).toBe(false);
+  });
+
+  test("return() drops a Receiver a next() is waiting on", async () => {
+    const sensor = new mod.Sensor();
+    const late = sensor.late(300);
+    const next = late.next();
+    await late.return();
+    expect(await next).toEqual({ done: true, value: undefined });
+    await new Promise((resolve) => setTimeout(resolve, 400));
+    expect(sensor.produced).toBe(0);
+  });
+});
@@ -0,0 +1,104 @@
+const mod = require("./index.node");
+
+describe("Counter", () => {
+  test("exported with the constructor's js_name", () => {
+    expect(mod.MutexCounter).toBeDefined();
+    expect(mod.Counter).toBeUndefined();
+  });
+
+  test("getter with js_name", () => {
+    const counter = new mod.MutexCounter(7);
+    expect(counter.value).toBe(7);
+  });
+
+  test("'&mut self' methods with a Mutex", () => {
+    const counter = new mod.MutexCounter(2);
+    expect(counter.increment(3)).toBe(5);
+    expect(counter.increment(1)).toBe(6);
+    expect(counter.count()).toBe(6);
+  });
+
+  test("re-entrant calls throw instead of deadlocking", () => {
+    const counter = new mod.MutexCounter(0);
+    expect(() => counter.incrementWith(() => counter.count())).toThrow(
+      "Failed to borrow Counter: already borrowed"
+    );
+    expect(counter.count()).toBe(1);
+  });
+
+  test("async '&mut self' methods resolve with the result", async () => {
+    const counter = new mod.MutexCounter(2);
+    const p = counter.incrementAsync(3);
+    expect(p).toBeInstanceOf(Promise);
+    await expect(p).resolves.toBe(5);
+    expect(counter.count()).toBe(5);
+    await expect(counter.decrementAsync(4)).resolves.toBe(1);
+  });
+
+  test("blocking '&mut self' methods", async () => {
+    const counter = new mod.MutexCounter(1);
+    const p = counter.incrementBlocking(2);
+    expect(p).toBeInstanceOf(Promise);
+    await expect(p).resolves.toBe(3);
+    expect(counter.count()).toBe(3);
+  });
+
+  test("sync methods throw while a blocking call holds the lock", async () => {
+    const counter = new mod.MutexCounter(1);
+    let thrown;
+    const held = counter.hold(200, () => {
+      try {
+        counter.count();
+      } catch (e) {
+        thrown = e;
+      }
+    });
+    await expect(held).resolves.toBe(1);
+    expect(thrown).toBeInstanceOf(Error);
+    expect(thrown.message).toBe("Failed to borrow Counter: already borrowed");
+    expect(counter.count()).toBe(1);
+  });
+
+  test("waiting for the lock doesn't hold up other async calls", async () => {
+    const counter = new mod.MutexCounter(0);
+    const order = [];
+    let waiting;
+    const held = counter.hold(300, () => {
+      // more waiters than executor threads.
+      waiting = Array.from({ length: 8 }, () => counter.incrementAsync(1));
+      mod.addAsync(1, 2).then(() => order.push("add"));
+    });
+    await held.then(() => order.push("held"));
+    const counts = await Promise.all(waiting);
+    expect(order).toEqual(["add", "held"]);
+    expect(counts.sort((a, b) => a - b)).toEqual([1, 2, 3, 4, 5, 6, 7, 8]);
+  });
+
+  test("async methods reject with the Err display", async () => {
+    const counter = new mod.MutexCounter(1);
+    await expect(counter.decrementAsync(2)).rejects.toThrow(
+      "Can't decrement 1 by 2"
+    );
+    expect(counter.count()).toBe(1);
+  });
+
+  test("items of the 'reset' part are on the same class", () => {
+    const counter = new mod.MutexCounter(4);
+    expect(counter.isReset).toBe(false);
+    expect(counter.reset()).toBe(4);
+    expect(counter.isReset).toBe(true);
+    expect(counter.value).toBe(0);
+    expect(mod.MutexCounter.START).toBe(0);
+    expect(mod.MutexCounter.maxCount()).toBe(4294967295);
+  });
+});
+
+test("async 'add_async' function", async () => {
+  await expect(mod.addAsync(1, 2)).resolves.toBe(3);
+});
+
+test("a panicking async function rejects with the message", async () => {
+  await expect(mod.divideAsync(1, 0)).rejects.toThrow(
+    "The call panicked: attempt to divide by zero"
+  );
+});
@@ -0,0 +1,141 @@
+const mod = require("./index.node");
+
+describe("CameraHandle", () => {
+  test("exported with the impl_block's js_name", () => {
+    expect(mod.CameraHandle).toBeUndefined();
+    const camera = new mod.Camera("front");
+    expect(camera.position).toBe("front");
+  });
+
+  test("async methods on a shared impl_block", async () => {
+    const camera = new mod.Camera("back");
+    await expect(camera.capture("shot")).resolves.toBe("back-shot");
+  });
+
+  test("blocking methods with throw_on_err", async () => {
+    const camera = new mod.Camera("back");
+    await expect(camera.readFrame(2)).resolves.toBe("back-frame-2");
+    await expect(camera.readFrame(11)).rejects.toThrow(
+      "No frame 11 for the back camera"
+    );
+  });
+
+  test("blocking static methods that return 'Self'", async () => {
+    const camera = await mod.Camera.probe("front");
+    expect(camera).toBeInstanceOf(mod.Camera);
+    expect(camera.position).toBe("front");
+    await expect(camera.capture("shot")).resolves.toBe("front-shot");
+  });
+
+  test("async constructor exported as 'create'", async () => {
+    const p = mod.Camera.create("top");
+    expect(p).toBeInstanceOf(Promise);
+    const camera = await p;
+    expect(camera.position).toBe("top");
+  });
+
+  describe("AbortSignal", () => {
+    // Node 14 has no global AbortController, this mimics the parts used here.
+    const makeAbortController = () => {
+      if (typeof AbortController !== "undefined") {
+        return new AbortController();
+      }
+      const listeners = new Set();
+      const signal = {
+        aborted: false,
+        addEventListener: (type, listener) => listeners.add(listener),
+        removeEventListener: (type, listener) => listeners.delete(listener),
+      };
+      const abort = () => {
+        signal.aborted = true;
+        listeners.forEach((listener) => listener());
+        listeners.clear();
+      };
+      return { signal, abort };
+    };
+
+    test("aborting an async method rejects with an AbortError", async () => {
+      const camera = new mod.Camera("front");
+      const controller = makeAbortController();
+      const p = camera.record(controller.signal);
+      controller.abort();
+      await expect(p).rejects.toMatchObject({ name: "AbortError" });
+    });
+
+    test("takes an options object with a signal", async () => {
+      const camera = new mod.Camera("front");
+      const controller = makeAbortController();
+      const p = camera.record({ signal: controller.signal });
+      controller.abort();
+      await expect(p).rejects.toMatchObject({ name: "AbortError" });
+    });
+
+    test("the signal is optional", async () => {
+      const camera = new mod.Camera("front");
+      await expect(camera.scan(2)).resolves.toBe(2);
+      const controller = makeAbortController();
+      await expect(camera.scan(2, controller.signal)).resolves.toBe(2);
+    });
+
+    test("aborting a blocking method rejects with an AbortError", async () => {
+      const camera = new mod.Camera("front");
+      const controller = makeAbortController();
+      const p = camera.scan(1000, controller.signal);
+      controller.abort();
+      await expect(p).rejects.toMatchObject({
+        name: "AbortError",
+        message: "The operation was aborted",
+      });
+    });
+
+    test("an already aborted signal rejects right away", async () => {
+      const camera = new mod.Camera("front");
+      const controller = makeAbortController();
+      controller.abort();
+      const p = camera.scan(1000, controller.signal);
+      await expect(p).rejects.toMatchObject({ name: "AbortError" });
+    });
+
+    test("aborting rejects without waiting for the blocking body", async () => {
+      const camera = new mod.Camera("front");
+      const controller = makeAbortController();
+      const start = Date.now();
+      const p = camera.expose(1000, controller.signal);
+      setTimeout(() => controller.abort(), 10);
+      await expect(p).rejects.toMatchObject({ name: "AbortError" });
+      expect(Date.now() - start).toBeLessThan(500);
+    });
+
+    test("the abort listener is removed once the call settles", async () => {
+      const camera = new mod.Camera("front");
+      const listeners = new Map();
+      const signal = {
+        aborted: false,
+        addEventListener: (type, listener, options) =>
+          listeners.set(listener, options),
+        removeEventListener: (type, listener) => listeners.delete(listener),
+      };
+      const p = camera.scan(2, signal);
+      expect([...listeners.values()]).toEqual([{ once: true }]);
+      await expect(p).resolves.toBe(2);
+      expect(listeners.size).toBe(0);
+    });
+  });
+
+  test("async static methods", async () => {
+    await expect(mod.Camera.listPositions()).resolves.toEqual([
+      "front",
+      "back",
+    ]);
+  });
+});
+
+test("blocking 'checksum' function", async () => {
+  await expect(mod.checksum("ab")).resolves.toBe(195);
+});
+
+test("a panicking blocking function rejects with the message", async () => {
+  await expect(mod.divide(1, 0)).rejects.toThrow(
+    "The call panicked: attempt to divide by zero"
+  );
+});
@@ -0,0 +1,45 @@
+const mod = require("./index.node");
+
+test("a ThreadsafeCallback is called from a rust thread", async () => {
+  const ticks = await new Promise((resolve) => {
+    const ticks = [];
+    mod.tick("tick-", 3, (label, tick) => {
+      ticks.push(label + tick);
+      if (ticks.length === 3) {
+        resolve(ticks);
+      }
+    });
+  });
+  expect(ticks).toEqual(["tick-0", "tick-1", "tick-2"]);
+});
+
+describe("JsFn", () => {
+  test("the JS function is called with the args and its return", () => {
+    const calls = [];
+    const kept = mod.keepNumbers([1, 2, 3, 4], (n, idx) => {
+      calls.push([n, idx]);
+      return n % 2 === 0;
+    });
+    expect(kept).toEqual([2, 4]);
+    expect(calls).toEqual([
+      [1, 0],
+      [2, 1],
+      [3, 2],
+      [4, 3],
+    ]);
+  });
+
+  test("a throw in the JS function is an Err in rust", () => {
+    expect(() =>
+      mod.keepNumbers([1], () => {
+        throw new Error("boom");
+      })
+    ).toThrow("The JS function threw: boom");
+  });
+
+  test("a return of the wrong type is an Err in rust", () => {
+    expect(() => mod.keepNumbers([1], () => "yes")).toThrow(
+      "The JS function returned an invalid value"
+    );
+  });
+});
@@ -0,0 +1,37 @@
+const mod = require("./index.node");
+
+describe("Version derive_js", () => {
+  test("Display is toString", () => {
+    const version = new mod.Version(1, 2, 3);
+    expect(version.toString()).toBe("1.2.3");
+    expect(`v${version}`).toBe("v1.2.3");
+  });
+
+  test("Clone gives a new object", () => {
+    const version = new mod.Version(1, 2, 3);
+    const copy = version.clone();
+    expect(copy).not.toBe(version);
+    expect(copy).toBeInstanceOf(mod.Version);
+    expect(copy.major).toBe(1);
+    expect(copy.equals(version)).toBe(true);
+  });
+
+  test("PartialEq is equals", () => {
+    const version = new mod.Version(1, 2, 3);
+    expect(version.equals(new mod.Version(1, 2, 3))).toBe(true);
+    expect(version.equals(new mod.Version(1, 2, 4))).toBe(false);
+    expect(version.equals(version)).toBe(true);
+    expect(() => version.equals(new mod.Playlist([]))).toThrow();
+  });
+
+  test("Ord is a static compare for sort", () => {
+    const versions = [
+      new mod.Version(1, 10, 0),
+      new mod.Version(0, 9, 1),
+      new mod.Version(1, 2, 3),
+    ];
+    versions.sort(mod.Version.compare);
+    expect(versions.map(String)).toEqual(["0.9.1", "1.2.3", "1.10.0"]);
+    expect(mod.Version.compare(versions[0], versions[0])).toBe(0);
+  });
+});
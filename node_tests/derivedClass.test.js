@@ -39,6 +39,11 @@ describe("TestStruct", () => {
       expect(obj.takeNumericReturnResult(123, -3123)).toBe(-3000);
     });
 
+    it("calls 'take_numeric' with '&mut self'", () => {
+      obj.takeNumericReturnResult(1, 41);
+      expect(obj.getMyVal()).toBe(41);
+    });
+
     it("calls 'take_numeric' and throws", () => {
       expect(() => obj.takeNumericReturnResult(0, -1)).toThrow(
         "Second arg was -1"
@@ -52,10 +57,81 @@ describe("TestStruct", () => {
     });
   });
 
+  describe("accessors", () => {
+    const obj = new mod.TestStruct(p, map);
+
+    it("gets and sets 'my_val'", () => {
+      expect(obj.myVal).toBe(0);
+      obj.myVal = 12;
+      expect(obj.myVal).toBe(12);
+      expect(obj.getMyVal()).toBe(12);
+    });
+
+    it("gets the read-only 'a_path'", () => {
+      expect(obj.aPath).toBe(p);
+      const descriptor = Object.getOwnPropertyDescriptor(
+        Object.getPrototypeOf(obj),
+        "aPath"
+      );
+      expect(descriptor.get).toBeDefined();
+      expect(descriptor.set).toBeUndefined();
+    });
+  });
+
+  test("'describe' exported with js_name 'toString'", () => {
+    const obj = new mod.TestStruct(p, map);
+    expect(obj.toString()).toBe(`TestStruct(${p})`);
+    expect(`${obj}`).toBe(`TestStruct(${p})`);
+    expect(obj.describe).toBeUndefined();
+  });
+
+  describe("static methods", () => {
+    it("calls 'version'", () => {
+      expect(mod.TestStruct.version()).toBe("1.0.0");
+    });
+
+    it("calls 'from_path' that returns Self", () => {
+      const obj = mod.TestStruct.fromPath(p);
+      expect(obj).toBeInstanceOf(mod.TestStruct);
+      expect(obj.aPath).toBe(p);
+      expect(obj.plainMethod(1)).toBe("to-str-1-NONE");
+    });
+
+    it("is not on the prototype", () => {
+      const obj = new mod.TestStruct(p, map);
+      expect(obj.version).toBeUndefined();
+    });
+  });
+
+  describe("constants", () => {
+    it("are set on the constructor", () => {
+      expect(mod.TestStruct.MAX_SIZE).toBe(100);
+      expect(mod.TestStruct.KINDS).toEqual(["camel", "snake"]);
+    });
+
+    it("are read-only", () => {
+      const descriptor = Object.getOwnPropertyDescriptor(
+        mod.TestStruct,
+        "MAX_SIZE"
+      );
+      expect(descriptor.writable).toBe(false);
+      expect(descriptor.configurable).toBe(false);
+    });
+  });
+
+  test("borrow conflict throws instead of panicking", () => {
+    const obj = new mod.TestStruct(p, map);
+    expect(() => obj.callWhileBorrowed(() => obj.getMyVal())).toThrow(
+      "Failed to borrow TestStruct: already mutably borrowed"
+    );
+    expect(obj.getMyVal()).toBe(1);
+  });
+
   test("to_js_obj via the 'test' rust function", async () => {
     const path_num = 3;
     const p = `random_path_${path_num}`;
     const ts = await mod.test(3);
+    expect(ts).toBeInstanceOf(mod.TestStruct);
     const arg = 12.8;
     const res = ts.plainMethod(arg);
     expect(res).toBe(`to-str-${arg}-NONE`);
@@ -0,0 +1,58 @@
+const mod = require("./index.node");
+
+describe("Connection events", () => {
+  const open = (connection, address, chunks) =>
+    new Promise((resolve) => {
+      connection.once("end", resolve);
+      connection.open(address, chunks);
+    });
+
+  test("listeners get the events emitted from a rust thread", async () => {
+    const connection = new mod.Connection();
+    const connected = jest.fn();
+    const data = [];
+    connection
+      .on("connected", connected)
+      .on("data", (chunk) => data.push(chunk));
+    await open(connection, "localhost", ["a", "b"]);
+    expect(connected).toHaveBeenCalledWith({ address: "localhost" });
+    expect(data).toEqual(["a", "b"]);
+  });
+
+  test("once listeners are called once and off removes listeners", async () => {
+    const connection = new mod.Connection();
+    const connected = jest.fn();
+    const data = jest.fn();
+    connection.once("connected", connected);
+    connection.on("data", data);
+    connection.off("data", data);
+    await open(connection, "localhost", ["a"]);
+    await open(connection, "localhost", ["b"]);
+    expect(connected).toHaveBeenCalledTimes(1);
+    expect(data).not.toHaveBeenCalled();
+  });
+
+  test("removeAllListeners removes the listeners of an event", async () => {
+    const connection = new mod.Connection();
+    const data = [];
+    // captures its own instance, which keeps both alive until removed.
+    const onData = (chunk) => data.push([connection, chunk]);
+    const connected = jest.fn();
+    connection.on("data", onData).on("connected", connected);
+    expect(connection.removeAllListeners("data")).toBe(connection);
+    await open(connection, "localhost", ["a"]);
+    expect(data).toEqual([]);
+    expect(connected).toHaveBeenCalledTimes(1);
+  });
+
+  test("removeAllListeners without an event removes them all", async () => {
+    const connection = new mod.Connection();
+    const connected = jest.fn();
+    const data = jest.fn();
+    connection.on("connected", connected).on("data", data);
+    connection.removeAllListeners();
+    await open(connection, "localhost", ["a"]);
+    expect(connected).not.toHaveBeenCalled();
+    expect(data).not.toHaveBeenCalled();
+  });
+});
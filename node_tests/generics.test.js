@@ -0,0 +1,20 @@
+const mod = require("./index.node");
+
+describe("Report instantiations", () => {
+  test("one class per instantiation", () => {
+    const csv = new mod.CsvReport();
+    const tsv = new mod.TsvReport();
+    expect(csv.push(1)).toBe(1);
+    expect(csv.push(2.5)).toBe(2);
+    tsv.push(1);
+    tsv.push(2.5);
+    expect(csv.render()).toBe("1,2.5");
+    expect(tsv.render()).toBe("1\t2.5");
+    expect(mod.Report).toBeUndefined();
+  });
+
+  test("instances of one instantiation aren't the other's", () => {
+    const csv = new mod.CsvReport();
+    expect(() => mod.TsvReport.prototype.render.call(csv)).toThrow();
+  });
+});
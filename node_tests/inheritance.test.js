@@ -0,0 +1,93 @@
+const mod = require("./index.node");
+
+describe("Circle extends Shape", () => {
+  test("base class works on its own", () => {
+    const shape = new mod.Shape("square");
+    expect(shape.moveOnce()).toBe(1);
+    expect(shape.describe()).toBe("square moved 1 times");
+    expect(shape.area()).toBe(0);
+  });
+
+  test("instanceof the base class", () => {
+    const circle = new mod.Circle(2);
+    expect(circle).toBeInstanceOf(mod.Circle);
+    expect(circle).toBeInstanceOf(mod.Shape);
+    expect(Object.getPrototypeOf(mod.Circle.prototype)).toBe(
+      mod.Shape.prototype
+    );
+  });
+
+  test("inherited methods and getters borrow the base", () => {
+    const circle = new mod.Circle(2);
+    expect(circle.name).toBe("circle");
+    expect(circle.moveOnce()).toBe(1);
+    expect(circle.moveOnce()).toBe(2);
+    expect(circle.describe()).toBe("circle moved 2 times");
+    expect(circle.radius).toBe(2);
+  });
+
+  test("methods of the derived class override the base ones", () => {
+    const circle = new mod.Circle(2);
+    expect(circle.area()).toBeCloseTo(Math.PI * 4);
+    expect(mod.Shape.prototype.area.call(circle)).toBe(0);
+  });
+
+  test("static methods are inherited", () => {
+    expect(mod.Circle.defaultName()).toBe("shape");
+  });
+
+  test("base methods throw on unrelated objects", () => {
+    const obj = {};
+    expect(() => mod.Shape.prototype.describe.call(obj)).toThrow();
+  });
+});
+
+describe("JS subclasses", () => {
+  class Square extends mod.Shape {
+    constructor(side) {
+      super("square");
+      this.side = side;
+    }
+
+    area() {
+      return this.side * this.side;
+    }
+  }
+
+  class DoubleCounter extends mod.MutexCounter {
+    double() {
+      return this.increment(this.count());
+    }
+  }
+
+  test("constructor sets up the subclass' object", () => {
+    const square = new Square(3);
+    expect(square).toBeInstanceOf(Square);
+    expect(square).toBeInstanceOf(mod.Shape);
+    expect(square.side).toBe(3);
+    expect(square.moveOnce()).toBe(1);
+    expect(square.describe()).toBe("square moved 1 times");
+
+    const counter = new DoubleCounter(2);
+    expect(counter.double()).toBe(4);
+    expect(counter.value).toBe(4);
+  });
+
+  test("constructor throws without 'new'", () => {
+    expect(() => mod.Shape("square")).toThrow(
+      "Class constructor cannot be invoked without 'new'"
+    );
+  });
+
+  test("rust calls the JS override of a virtual method", () => {
+    expect(new Square(3).summary()).toBe("square with an area of 9.00");
+  });
+
+  test("rust calls its own virtual method without an override", () => {
+    expect(new mod.Shape("dot").summary()).toBe("dot with an area of 0.00");
+  });
+
+  test("rust calls the override of a derived rust class", () => {
+    expect(new mod.Circle(1).summary()).toBe("circle with an area of 3.14");
+  });
+});
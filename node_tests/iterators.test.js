@@ -0,0 +1,16 @@
+const mod = require("./index.node");
+
+describe("Iterators", () => {
+  test("an endless iterator only runs for the items JS asks for", () => {
+    const naturals = mod.naturals();
+    const firsts = [];
+    for (const n of naturals) {
+      if (n > 2) {
+        break;
+      }
+      firsts.push(n);
+    }
+    expect(firsts).toEqual([0, 1, 2]);
+    expect(naturals.next().value).toBe(4);
+  });
+});
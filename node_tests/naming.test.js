@@ -0,0 +1,37 @@
+const mod = require("./index.node");
+
+test("'initialize_module' exported with js_name 'init'", () => {
+  expect(mod.init()).toBe("initialized");
+  expect(mod.initializeModule).toBeUndefined();
+});
+
+test("'create_widget' exported with rename_all 'PascalCase'", () => {
+  expect(mod.CreateWidget("a")).toBe("widget-a");
+});
+
+describe("SnakeCaseStruct", () => {
+  const obj = new mod.SnakeCaseStruct(50);
+
+  test("method with rename_all 'snake_case'", () => {
+    expect(obj.frame_time_ms()).toBe(20);
+    expect(obj.frameTimeMs).toBeUndefined();
+  });
+
+  test("getter with rename_all 'snake_case'", () => {
+    expect(obj.frame_rate).toBe(50);
+  });
+
+  test("static method and constant with rename_all 'snake_case'", () => {
+    expect(mod.SnakeCaseStruct.default_frame_rate()).toBe(30);
+    expect(mod.SnakeCaseStruct.max_frame_rate).toBe(120);
+  });
+
+  test("js_name takes precedence", () => {
+    expect(obj.toString()).toBe("50 fps");
+  });
+
+  test("the 'timing' part uses the same rename_all", () => {
+    expect(obj.frames_in(2)).toBe(100);
+    expect(mod.SnakeCaseStruct.min_frame_rate).toBe(1);
+  });
+});
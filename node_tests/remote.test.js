@@ -0,0 +1,21 @@
+const mod = require("./index.node");
+
+describe("Queue wraps a remote VecDeque", () => {
+  test("declared methods are forwarded", () => {
+    const queue = new mod.Queue();
+    queue.pushBack(1);
+    queue.pushBack(2.5);
+    expect(queue.len()).toBe(2);
+    expect(queue.popFront()).toBe(1);
+    expect(queue.popFront()).toBe(2.5);
+    expect(queue.popFront()).toBeNull();
+  });
+
+  test("a declared static method returns an instance", () => {
+    const queue = mod.Queue.withCapacity(8);
+    expect(queue).toBeInstanceOf(mod.Queue);
+    expect(queue.len()).toBe(0);
+    queue.pushBack(3);
+    expect(queue.popFront()).toBe(3);
+  });
+});
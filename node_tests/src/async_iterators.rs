@@ -0,0 +1,116 @@
+use neon::prelude::{Context, Finalize};
+use neon_class_macros::neon_class;
+use neon_class_runtime::async_iter::Stream;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::mpsc::{self, sync_channel};
+use std::sync::Arc;
+use std::task::{Context as TaskCx, Poll};
+
+/// Counts down to 1, to test methods returning a `Stream`.
+struct Countdown(u32);
+
+impl Stream for Countdown {
+    type Item = u32;
+
+    fn poll_next(mut self: Pin<&mut Self>, _cx: &mut TaskCx<'_>) -> Poll<Option<u32>> {
+        if self.0 == 0 {
+            return Poll::Ready(None);
+        }
+        self.0 -= 1;
+        Poll::Ready(Some(self.0 + 1))
+    }
+}
+
+/// Never yields, it only tells when it's dropped.
+struct Idle(Arc<AtomicBool>);
+
+impl Stream for Idle {
+    type Item = u32;
+
+    fn poll_next(self: Pin<&mut Self>, _cx: &mut TaskCx<'_>) -> Poll<Option<u32>> {
+        Poll::Pending
+    }
+}
+
+impl Drop for Idle {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Struct to test async iterators, its samples are made on another thread as JS awaits them.
+#[derive(neon_class_macros::Class)]
+pub struct Sensor {
+    produced: Arc<AtomicU32>,
+    producing: Arc<AtomicBool>,
+}
+
+impl Finalize for Sensor {}
+
+#[neon_class(impl_block)]
+impl Sensor {
+    #[neon_class(constructor)]
+    pub fn new() -> Result<Self, String> {
+        Ok(Self {
+            produced: Arc::new(AtomicU32::new(0)),
+            producing: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    /// Never ends, each sample is only made once the previous one was taken.
+    #[neon_class(method)]
+    pub fn samples(&self) -> mpsc::Receiver<u32> {
+        let (tx, rx) = sync_channel(0);
+        let produced = Arc::clone(&self.produced);
+        let producing = Arc::clone(&self.producing);
+        producing.store(true, Ordering::SeqCst);
+        std::thread::spawn(move || {
+            for sample in 0.. {
+                // fails once the JS side is done with the samples.
+                if tx.send(sample).is_err() {
+                    break;
+                }
+                produced.fetch_add(1, Ordering::SeqCst);
+            }
+            producing.store(false, Ordering::SeqCst);
+        });
+        rx
+    }
+
+    #[neon_class(method)]
+    pub fn countdown(&self, from: u32) -> impl Stream<Item = u32> {
+        Countdown(from)
+    }
+
+    /// Keeps `producing` until JS is done with it.
+    #[neon_class(method)]
+    pub fn idle(&self) -> impl Stream<Item = u32> {
+        self.producing.store(true, Ordering::SeqCst);
+        Idle(Arc::clone(&self.producing))
+    }
+
+    /// Sends a single sample after `delay_ms`, which only counts as produced if it was received.
+    #[neon_class(method)]
+    pub fn late(&self, delay_ms: u64) -> mpsc::Receiver<u32> {
+        let (tx, rx) = mpsc::channel();
+        let produced = Arc::clone(&self.produced);
+        std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(delay_ms));
+            if tx.send(0).is_ok() {
+                produced.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+        rx
+    }
+
+    #[neon_class(getter)]
+    pub fn produced(&self) -> u32 {
+        self.produced.load(Ordering::SeqCst)
+    }
+
+    #[neon_class(getter)]
+    pub fn producing(&self) -> bool {
+        self.producing.load(Ordering::SeqCst)
+    }
+}
@@ -0,0 +1,114 @@
+use neon::prelude::{Context, Finalize, FunctionContext, Handle, JsFunction, JsResult, JsValue};
+use neon_class_macros::neon_class;
+use neon_class_runtime::ThreadsafeCallback;
+
+#[neon_class_macros::function]
+pub async fn add_async(a: u32, b: u32) -> u32 {
+    a + b
+}
+
+/// Panics when `b` is 0, which rejects the promise.
+#[neon_class_macros::function]
+pub async fn divide_async(a: u32, b: u32) -> u32 {
+    a / b
+}
+
+/// This struct is to test a `mutable` impl block that stores `Self` in a [`Mutex`](std::sync::Mutex).
+#[derive(neon_class_macros::Class)]
+pub struct Counter {
+    count: u32,
+}
+
+impl Finalize for Counter {}
+
+#[neon_class(impl_block, mutable = "Mutex", parts(reset))]
+impl Counter {
+    #[neon_class(constructor, js_name = "MutexCounter")]
+    pub fn new_counter(start: u32) -> Result<Self, String> {
+        Ok(Self { count: start })
+    }
+
+    #[neon_class(method)]
+    pub fn increment(&mut self, by: u32) -> u32 {
+        self.count += by;
+        self.count
+    }
+
+    #[neon_class(method)]
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// Calls back into JS while `self` is locked. If the callback tries to use this same
+    /// counter, the JS side gets an exception instead of a deadlock.
+    #[neon_class(method)]
+    pub fn increment_with<'ctx>(
+        &mut self,
+        cx: &mut FunctionContext<'ctx>,
+        cb: Handle<'ctx, JsFunction>,
+    ) -> JsResult<'ctx, JsValue> {
+        self.count += 1;
+        let this = cx.undefined();
+        cb.call(cx, this, std::iter::empty::<Handle<JsValue>>())
+    }
+
+    #[neon_class(method)]
+    pub async fn increment_async(&mut self, by: u32) -> u32 {
+        self.count += by;
+        self.count
+    }
+
+    #[neon_class(method, blocking)]
+    pub fn increment_blocking(&mut self, by: u32) -> u32 {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        self.count += by;
+        self.count
+    }
+
+    /// Keeps the counter locked for `ms` milliseconds, `on_locked` is called once it is.
+    #[neon_class(method, blocking)]
+    pub fn hold(&mut self, ms: u32, on_locked: ThreadsafeCallback<()>) -> u32 {
+        on_locked.call(());
+        std::thread::sleep(std::time::Duration::from_millis(ms.into()));
+        self.count
+    }
+
+    #[neon_class(method)]
+    pub async fn decrement_async(&mut self, by: u32) -> Result<u32, String> {
+        self.count = self
+            .count
+            .checked_sub(by)
+            .ok_or_else(|| format!("Can't decrement {} by {}", self.count, by))?;
+        Ok(self.count)
+    }
+
+    #[neon_class(getter, js_name = "value")]
+    pub fn current_count(&self) -> u32 {
+        self.count
+    }
+}
+
+/// Adds its items to the `MutexCounter` class of the block above, `mutable` lets it have `&mut self`
+/// methods.
+#[neon_class(impl_block, part = "reset", mutable)]
+impl Counter {
+    #[neon_class(constant)]
+    pub const START: u32 = 0;
+
+    #[neon_class(method)]
+    pub fn reset(&mut self) -> u32 {
+        let count = self.count;
+        self.count = Self::START;
+        count
+    }
+
+    #[neon_class(getter)]
+    pub fn is_reset(&self) -> bool {
+        self.count == Self::START
+    }
+
+    #[neon_class(static_method)]
+    pub fn max_count() -> u32 {
+        u32::MAX
+    }
+}
@@ -0,0 +1,88 @@
+use neon::prelude::{Context, Finalize};
+use neon_class_macros::neon_class;
+use neon_class_runtime::{AbortError, AbortToken};
+
+#[neon_class_macros::function(blocking)]
+pub fn checksum(data: String) -> u32 {
+    data.bytes().map(u32::from).sum()
+}
+
+/// Panics when `b` is 0, which rejects the promise.
+#[neon_class_macros::function(blocking)]
+pub fn divide(a: u32, b: u32) -> u32 {
+    a / b
+}
+
+/// This struct is to test exporting a class with a different name than the rust struct.
+#[derive(neon_class_macros::Class)]
+pub struct CameraHandle {
+    position: String,
+}
+
+impl Finalize for CameraHandle {}
+
+#[neon_class(impl_block, js_name = "Camera", shared)]
+impl CameraHandle {
+    #[neon_class(constructor)]
+    pub fn open(position: String) -> Result<Self, String> {
+        Ok(Self { position })
+    }
+
+    #[neon_class(getter)]
+    pub fn position(&self) -> String {
+        self.position.clone()
+    }
+
+    #[neon_class(async_constructor)]
+    pub async fn connect(position: String) -> Result<Self, String> {
+        Self::open(position)
+    }
+
+    #[neon_class(method)]
+    pub async fn capture(&self, label: String) -> String {
+        format!("{}-{}", self.position, label)
+    }
+
+    #[neon_class(method, blocking, throw_on_err)]
+    pub fn read_frame(&self, index: u32) -> Result<String, String> {
+        if index > 10 {
+            return Err(format!(
+                "No frame {} for the {} camera",
+                index, self.position
+            ));
+        }
+        Ok(format!("{}-frame-{}", self.position, index))
+    }
+
+    /// Records until the JS side aborts the call.
+    #[neon_class(method)]
+    pub async fn record(&self, _signal: AbortToken) -> u32 {
+        std::future::pending::<u32>().await
+    }
+
+    #[neon_class(method, blocking, throw_on_err)]
+    pub fn scan(&self, frames: u32, signal: AbortToken) -> Result<u32, AbortError> {
+        for _ in 0..frames {
+            signal.check()?;
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        Ok(frames)
+    }
+
+    /// Doesn't check the signal, an abort only rejects the promise.
+    #[neon_class(method, blocking)]
+    pub fn expose(&self, ms: u32, _signal: AbortToken) -> u32 {
+        std::thread::sleep(std::time::Duration::from_millis(ms.into()));
+        ms
+    }
+
+    #[neon_class(static_method, blocking)]
+    pub fn probe(position: String) -> Result<Self, String> {
+        Self::open(position)
+    }
+
+    #[neon_class(static_method)]
+    pub async fn list_positions() -> Vec<String> {
+        vec!["front".to_string(), "back".to_string()]
+    }
+}
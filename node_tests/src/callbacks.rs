@@ -0,0 +1,28 @@
+use neon::prelude::{Context, FunctionContext};
+use neon_class_runtime::{CallError, JsFn, ThreadsafeCallback};
+
+/// Calls `on_tick` from another thread, once per tick.
+#[neon_class_macros::function]
+pub fn tick(label: String, times: u32, on_tick: ThreadsafeCallback<(String, u32)>) {
+    std::thread::spawn(move || {
+        for tick in 0..times {
+            on_tick.call((label.clone(), tick));
+        }
+    });
+}
+
+/// Keeps the numbers `keep` returns `true` for, it gets each number with its index.
+#[neon_class_macros::function(throw_on_err)]
+pub fn keep_numbers<'ctx>(
+    cx: &mut FunctionContext<'ctx>,
+    numbers: Vec<u32>,
+    keep: JsFn<'ctx, (u32, usize), bool>,
+) -> Result<Vec<u32>, CallError> {
+    let mut kept = Vec::new();
+    for (idx, number) in numbers.into_iter().enumerate() {
+        if keep.call(cx, (number, idx))? {
+            kept.push(number);
+        }
+    }
+    Ok(kept)
+}
@@ -0,0 +1,35 @@
+use neon::prelude::{Context, Finalize};
+use neon_class_macros::neon_class;
+
+/// Struct to test `derive_js`, its rust traits are exported as JS methods.
+#[derive(neon_class_macros::Class, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version {
+    major: u32,
+    minor: u32,
+    patch: u32,
+}
+
+impl Finalize for Version {}
+
+impl std::fmt::Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+#[neon_class(impl_block, derive_js(Display, Clone, PartialEq, Ord))]
+impl Version {
+    #[neon_class(constructor)]
+    pub fn new(major: u32, minor: u32, patch: u32) -> Result<Self, String> {
+        Ok(Self {
+            major,
+            minor,
+            patch,
+        })
+    }
+
+    #[neon_class(getter)]
+    pub fn major(&self) -> u32 {
+        self.major
+    }
+}
@@ -1,10 +1,9 @@
 use neon::prelude::{
-    Context, Finalize, FunctionContext, Handle, JsFunction, JsPromise, JsResult, Object,
+    Context, Finalize, FunctionContext, Handle, JsFunction, JsPromise, JsResult, JsValue, Object,
 };
 use neon::types::JsString;
 use neon_class_macros::neon_class;
 use serde::{Deserialize, Serialize};
-use std::cell::RefCell;
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -39,13 +38,19 @@ impl TryFrom<MapLike> for HashMap<String, PathBuf> {
 pub struct TestStruct {
     a_path: PathBuf,
     a_map: HashMap<String, PathBuf>,
-    my_val: RefCell<i32>,
+    my_val: i32,
 }
 
 impl Finalize for TestStruct {}
 
-#[neon_class(impl_block)]
+#[neon_class(impl_block, mutable)]
 impl TestStruct {
+    #[neon_class(constant)]
+    pub const MAX_SIZE: u32 = 100;
+
+    #[neon_class(constant)]
+    pub const KINDS: [&'static str; 2] = ["camel", "snake"];
+
     #[neon_class(constructor)]
     pub fn constructor(a_path: String, map_like: MapLike) -> Result<Self, String> {
         let a_map = map_like.try_into()?;
@@ -53,7 +58,7 @@ impl TestStruct {
         Ok(Self {
             a_path: a_path.into(),
             a_map,
-            my_val: RefCell::new(0),
+            my_val: 0,
         })
     }
 
@@ -101,15 +106,49 @@ impl TestStruct {
     }
 
     #[neon_class(method, throw_on_err)]
-    fn take_numeric_return_result(&self, u_32: u32, i_32: i32) -> Result<i32, &'static str> {
+    fn take_numeric_return_result(&mut self, u_32: u32, i_32: i32) -> Result<i32, &'static str> {
         if i_32 == -1 {
             Err("Second arg was -1")
         } else {
-            *self.my_val.borrow_mut() = i_32;
+            self.my_val = i_32;
             Ok(i_32 + u_32 as i32)
         }
     }
 
+    #[neon_class(method)]
+    fn get_my_val(&self) -> i32 {
+        self.my_val
+    }
+
+    #[neon_class(getter)]
+    fn my_val(&self) -> i32 {
+        self.my_val
+    }
+
+    #[neon_class(setter)]
+    fn set_my_val(&mut self, my_val: i32) {
+        self.my_val = my_val;
+    }
+
+    /// A getter without a setter is a read-only property on the JS side.
+    #[neon_class(getter)]
+    fn a_path(&self) -> String {
+        format!("{}", self.a_path.display())
+    }
+
+    /// Calls back into JS while `self` is mutably borrowed. If the callback tries to use this
+    /// same object, the JS side gets an exception instead of a panic.
+    #[neon_class(method)]
+    fn call_while_borrowed<'ctx>(
+        &mut self,
+        cx: &mut FunctionContext<'ctx>,
+        cb: Handle<'ctx, JsFunction>,
+    ) -> JsResult<'ctx, JsValue> {
+        self.my_val += 1;
+        let this = cx.undefined();
+        cb.call(cx, this, std::iter::empty::<Handle<JsValue>>())
+    }
+
     #[neon_class(method)]
     fn take_cx_but_return_native_val(&self, _cx: &mut FunctionContext, num: f64) -> String {
         let p = self.a_map.get("LE_KEY");
@@ -121,6 +160,21 @@ impl TestStruct {
         )
     }
 
+    #[neon_class(method, js_name = "toString")]
+    fn describe(&self) -> String {
+        format!("TestStruct({})", self.a_path.display())
+    }
+
+    #[neon_class(static_method)]
+    pub fn from_path(a_path: String) -> Result<Self, String> {
+        Self::constructor(a_path, MapLike { map: Vec::new() })
+    }
+
+    #[neon_class(static_method)]
+    pub fn version() -> String {
+        "1.0.0".to_string()
+    }
+
     /// This is just to make sure we can have non exported methods in between exported ones.
     #[allow(unused)]
     pub fn non_decorated_method(&self, p: String) {
@@ -1,2 +1,7 @@
+mod async_self_without_shared_error;
+mod invalid_arg_error;
+mod invalid_impl_block_arg_error;
 mod multiple_ctor_error;
+mod mut_self_in_part_error;
+mod mut_self_without_mutable_error;
 mod rename_macro_error;
@@ -0,0 +1,27 @@
+use neon::prelude::Finalize;
+use neon_class_macros::neon_class;
+
+#[allow(dead_code)]
+#[derive(neon_class_macros::Class)]
+pub struct TestStruct {
+    count: u32,
+}
+
+impl Finalize for TestStruct {}
+
+#[neon_class(impl_block)]
+impl TestStruct {
+    #[neon_class(constructor)]
+    pub fn constructor(count: u32) -> Result<Self, String> {
+        Ok(Self { count })
+    }
+
+    #[neon_class(method)]
+    pub fn increment(&mut self) {
+        self.count += 1;
+    }
+}
+
+// Needed for the try_build tests.
+#[allow(unused)]
+fn main() {}
@@ -0,0 +1,41 @@
+use neon::prelude::{Context, Finalize};
+use neon_class_macros::neon_class;
+use neon_class_runtime::events::EventSender;
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct Connected {
+    address: String,
+}
+
+/// Struct to test `events`, its events are emitted from another thread by the field named with
+/// `events = "emitter"`.
+#[derive(neon_class_macros::Class)]
+pub struct Connection {
+    emitter: EventSender,
+}
+
+impl Finalize for Connection {}
+
+#[neon_class(impl_block, events = "emitter")]
+impl Connection {
+    #[neon_class(constructor)]
+    pub fn new() -> Result<Self, String> {
+        Ok(Self {
+            emitter: EventSender::new(),
+        })
+    }
+
+    /// Emits `'connected'`, a `'data'` per chunk and then `'end'`.
+    #[neon_class(method)]
+    pub fn open(&self, address: String, chunks: Vec<String>) {
+        let events = self.emitter.clone();
+        std::thread::spawn(move || {
+            events.emit("connected", Connected { address });
+            for chunk in chunks {
+                events.emit("data", chunk);
+            }
+            events.emit("end", ());
+        });
+    }
+}
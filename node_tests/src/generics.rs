@@ -0,0 +1,60 @@
+use neon::prelude::{Context, Finalize};
+use neon_class_macros::neon_class;
+
+/// Renders the values of a [`Report`].
+pub trait Format: Send + 'static {
+    fn render(values: &[f64]) -> String;
+}
+
+pub struct Csv;
+
+impl Format for Csv {
+    fn render(values: &[f64]) -> String {
+        let values: Vec<String> = values.iter().map(f64::to_string).collect();
+        values.join(",")
+    }
+}
+
+pub struct Tsv;
+
+impl Format for Tsv {
+    fn render(values: &[f64]) -> String {
+        let values: Vec<String> = values.iter().map(f64::to_string).collect();
+        values.join("\t")
+    }
+}
+
+/// Generic struct exported as one JS class per `Format`.
+#[derive(neon_class_macros::Class)]
+pub struct Report<F: Format> {
+    values: Vec<f64>,
+    format: std::marker::PhantomData<F>,
+}
+
+impl<F: Format> Finalize for Report<F> {}
+
+#[neon_class(
+    impl_block,
+    mutable,
+    instantiate(Report<Csv> as "CsvReport", Report<Tsv> as "TsvReport")
+)]
+impl<F: Format> Report<F> {
+    #[neon_class(constructor)]
+    pub fn new() -> Result<Self, String> {
+        Ok(Self {
+            values: Vec::new(),
+            format: std::marker::PhantomData,
+        })
+    }
+
+    #[neon_class(method)]
+    pub fn push(&mut self, value: f64) -> u32 {
+        self.values.push(value);
+        self.values.len() as u32
+    }
+
+    #[neon_class(method)]
+    pub fn render(&self) -> String {
+        F::render(&self.values)
+    }
+}
@@ -0,0 +1,96 @@
+use neon::prelude::{Context, Finalize, FunctionContext, JsResult};
+use neon::types::JsString;
+use neon_class_macros::neon_class;
+
+/// Base class extended by [`Circle`].
+#[derive(neon_class_macros::Class)]
+pub struct Shape {
+    name: String,
+    moves: u32,
+}
+
+impl Finalize for Shape {}
+
+#[neon_class(impl_block, mutable, extendable)]
+impl Shape {
+    #[neon_class(constructor)]
+    pub fn new(name: String) -> Result<Self, String> {
+        Ok(Self { name, moves: 0 })
+    }
+
+    #[neon_class(method)]
+    pub fn describe(&self) -> String {
+        format!("{} moved {} times", self.name, self.moves)
+    }
+
+    /// JS subclasses can override this one, see `summary`.
+    #[neon_class(method, virtual)]
+    pub fn area(&self) -> f64 {
+        0.0
+    }
+
+    #[neon_class(method)]
+    pub fn summary<'ctx>(&self, cx: &mut FunctionContext<'ctx>) -> JsResult<'ctx, JsString> {
+        let this = cx.this();
+        let area = self.call_area(cx, this)?;
+        Ok(cx.string(format!("{} with an area of {:.2}", self.name, area)))
+    }
+
+    #[neon_class(method)]
+    pub fn move_once(&mut self) -> u32 {
+        self.moves += 1;
+        self.moves
+    }
+
+    #[neon_class(getter)]
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    #[neon_class(static_method)]
+    pub fn default_name() -> String {
+        "shape".to_string()
+    }
+}
+
+/// Derived class that gets the methods of [`Shape`] through its `shape` field.
+#[derive(neon_class_macros::Class)]
+pub struct Circle {
+    shape: Shape,
+    radius: f64,
+}
+
+impl Finalize for Circle {}
+
+impl AsRef<Shape> for Circle {
+    fn as_ref(&self) -> &Shape {
+        &self.shape
+    }
+}
+
+impl AsMut<Shape> for Circle {
+    fn as_mut(&mut self) -> &mut Shape {
+        &mut self.shape
+    }
+}
+
+#[neon_class(impl_block, mutable, extends = Shape)]
+impl Circle {
+    #[neon_class(constructor)]
+    pub fn new(radius: f64) -> Result<Self, String> {
+        Ok(Self {
+            shape: Shape::new("circle".to_string())?,
+            radius,
+        })
+    }
+
+    #[neon_class(method)]
+    pub fn area(&self) -> f64 {
+        std::f64::consts::PI * self.radius * self.radius
+    }
+
+    #[neon_class(getter)]
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+}
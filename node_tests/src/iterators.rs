@@ -0,0 +1,5 @@
+/// Never ends, JS only gets the numbers it pulls.
+#[neon_class_macros::function]
+pub fn naturals() -> Box<dyn Iterator<Item = u64>> {
+    Box::new(0..)
+}
@@ -1,7 +1,21 @@
 use crate::derived_class::{register_standalone_function, register_test};
 use neon::prelude::{ModuleContext, NeonResult};
 
+mod async_iterators;
+mod async_methods;
+mod blocking;
+mod callbacks;
+mod derive_js;
 mod derived_class;
+mod events;
+mod generics;
+mod inheritance;
+mod iterators;
+mod naming;
+mod remote;
+mod streams;
+mod symbols;
+mod trait_impls;
 
 // This is not really a feature, used just to signal the IDE to include the source files.
 #[cfg(feature = "error_try_builds")]
@@ -13,6 +27,36 @@ fn node_entrypoint(mut cx: ModuleContext) -> NeonResult<()> {
     register_standalone_function(&mut cx)?;
     derived_class::TestStruct::register_constructor(&mut cx)?;
     derived_class::TestStruct2::register_constructor_with_cx(&mut cx)?;
+
+    naming::register_initialize_module(&mut cx)?;
+    naming::register_create_widget(&mut cx)?;
+    naming::SnakeCaseStruct::register_with_frame_rate(&mut cx)?;
+    async_methods::register_add_async(&mut cx)?;
+    async_methods::register_divide_async(&mut cx)?;
+    async_methods::Counter::register_new_counter(&mut cx)?;
+    blocking::register_checksum(&mut cx)?;
+    blocking::register_divide(&mut cx)?;
+    blocking::CameraHandle::register_open(&mut cx)?;
+    trait_impls::Device::register_connect(&mut cx)?;
+    // a base class has to be registered before the classes that extend it.
+    inheritance::Shape::register_new(&mut cx)?;
+    inheritance::Circle::register_new(&mut cx)?;
+    // one class per instantiation of the generic `Report`.
+    generics::Report::<generics::Csv>::register_new(&mut cx)?;
+    generics::Report::<generics::Tsv>::register_new(&mut cx)?;
+    remote::Queue::register_new(&mut cx)?;
+    symbols::Playlist::register_new(&mut cx)?;
+    derive_js::Version::register_new(&mut cx)?;
+    iterators::register_naturals(&mut cx)?;
+    async_iterators::Sensor::register_new(&mut cx)?;
+    // the JS side gives it the `stream` module for the `Readable`s of `repeat_byte`.
+    neon_class_runtime::stream::register_streams(&mut cx)?;
+    streams::register_repeat_byte(&mut cx)?;
+    streams::register_write_greeting(&mut cx)?;
+    streams::register_write_lines(&mut cx)?;
+    events::Connection::register_new(&mut cx)?;
+    callbacks::register_tick(&mut cx)?;
+    callbacks::register_keep_numbers(&mut cx)?;
     Ok(())
 }
 // Hack so this file can be included in the src/lib.rs Examples section.
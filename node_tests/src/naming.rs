@@ -0,0 +1,64 @@
+use neon::prelude::{Context, Finalize};
+use neon_class_macros::neon_class;
+
+#[neon_class_macros::function(js_name = "init")]
+pub fn initialize_module() -> String {
+    "initialized".to_string()
+}
+
+#[neon_class_macros::function(rename_all = "PascalCase")]
+pub fn create_widget(name: String) -> String {
+    format!("widget-{}", name)
+}
+
+/// This struct is to test a `rename_all` naming convention for everything it exports.
+#[derive(neon_class_macros::Class)]
+pub struct SnakeCaseStruct {
+    frame_rate: u32,
+}
+
+impl Finalize for SnakeCaseStruct {}
+
+#[neon_class(impl_block, rename_all = "snake_case", parts(timing))]
+impl SnakeCaseStruct {
+    #[neon_class(constant)]
+    pub const MAX_FRAME_RATE: u32 = 120;
+
+    #[neon_class(constructor)]
+    pub fn with_frame_rate(frame_rate: u32) -> Result<Self, String> {
+        Ok(Self { frame_rate })
+    }
+
+    #[neon_class(method)]
+    pub fn frame_time_ms(&self) -> f64 {
+        1000.0 / self.frame_rate as f64
+    }
+
+    #[neon_class(getter)]
+    pub fn frame_rate(&self) -> u32 {
+        self.frame_rate
+    }
+
+    #[neon_class(static_method)]
+    pub fn default_frame_rate() -> u32 {
+        30
+    }
+
+    /// `js_name` takes precedence over `rename_all`.
+    #[neon_class(method, js_name = "toString")]
+    pub fn describe(&self) -> String {
+        format!("{} fps", self.frame_rate)
+    }
+}
+
+/// Its items are named with the `rename_all` of the block above.
+#[neon_class(impl_block, part = "timing")]
+impl SnakeCaseStruct {
+    #[neon_class(constant)]
+    pub const MIN_FRAME_RATE: u32 = 1;
+
+    #[neon_class(method)]
+    pub fn frames_in(&self, seconds: u32) -> u32 {
+        self.frame_rate * seconds
+    }
+}
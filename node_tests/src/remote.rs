@@ -0,0 +1,27 @@
+use neon::prelude::Context;
+use neon_class_macros::neon_class;
+
+/// Marker for a JS class that wraps a `VecDeque` from std, the methods declared without a body
+/// are forwarded to it.
+#[derive(neon_class_macros::Class)]
+pub struct Queue;
+
+#[neon_class(impl_block, mutable, remote = std::collections::VecDeque<f64>)]
+impl Queue {
+    #[neon_class(constructor)]
+    pub fn new() -> Result<Self, String> {
+        Ok(std::collections::VecDeque::new())
+    }
+
+    #[neon_class(static_method)]
+    pub fn with_capacity(capacity: usize) -> Self;
+
+    #[neon_class(method)]
+    pub fn push_back(&mut self, value: f64);
+
+    #[neon_class(method)]
+    pub fn pop_front(&mut self) -> Option<f64>;
+
+    #[neon_class(method)]
+    pub fn len(&self) -> usize;
+}
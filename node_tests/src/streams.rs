@@ -0,0 +1,25 @@
+use neon::prelude::Context;
+use std::io::{Read, Write};
+
+/// `count` times the given byte, read by JS as a `Readable`.
+#[neon_class_macros::function]
+pub fn repeat_byte(byte: u32, count: u32) -> impl Read + Send + 'static {
+    std::io::repeat(byte as u8).take(count as u64)
+}
+
+/// Writes on the JS thread, the lines reach the `Writable` once this returns.
+#[neon_class_macros::function]
+pub fn write_greeting(name: String, mut out: impl Write) {
+    writeln!(out, "hello {}", name).expect("Writes on the JS thread are only queued");
+}
+
+/// Writes on a worker thread, each line waits for the `Writable` to have room for it.
+#[neon_class_macros::function(blocking)]
+pub fn write_lines(count: u32, mut out: impl Write) -> u32 {
+    for line in 0..count {
+        if writeln!(out, "line {}", line).is_err() {
+            return line;
+        }
+    }
+    count
+}
@@ -0,0 +1,64 @@
+use neon::prelude::{
+    Context, Finalize, FunctionContext, Handle, JsFunction, JsResult, JsValue, Object,
+};
+use neon_class_macros::neon_class;
+
+/// Struct to test the methods set on symbols, its instances work with `for..of`, spread and
+/// `util.inspect`.
+#[derive(neon_class_macros::Class)]
+pub struct Playlist {
+    songs: Vec<String>,
+}
+
+impl Finalize for Playlist {}
+
+#[neon_class(impl_block)]
+impl Playlist {
+    #[neon_class(constructor)]
+    pub fn new(songs: Vec<String>) -> Result<Self, String> {
+        Ok(Self { songs })
+    }
+
+    /// Called by `for..of` and spread, iterates the songs of a JS array.
+    #[neon_class(method, symbol = "iterator")]
+    pub fn songs<'ctx>(&self, cx: &mut FunctionContext<'ctx>) -> JsResult<'ctx, JsValue> {
+        let songs = cx.empty_array();
+        for (i, song) in self.songs.iter().enumerate() {
+            let song = cx.string(song);
+            songs.set(cx, i as u32, song)?;
+        }
+        let values = songs
+            .get(cx, "values")?
+            .downcast_or_throw::<JsFunction, _>(cx)?;
+        values.call(cx, songs, std::iter::empty::<Handle<JsValue>>())
+    }
+
+    /// The number of songs when used as a number, their names otherwise.
+    #[neon_class(method, symbol = "toPrimitive")]
+    pub fn to_primitive<'ctx>(
+        &self,
+        cx: &mut FunctionContext<'ctx>,
+        hint: String,
+    ) -> JsResult<'ctx, JsValue> {
+        if hint == "number" {
+            Ok(cx.number(self.songs.len() as f64).upcast())
+        } else {
+            Ok(cx.string(self.songs.join(", ")).upcast())
+        }
+    }
+
+    #[neon_class(inspect)]
+    pub fn inspect(&self) -> String {
+        format!("Playlist({} songs)", self.songs.len())
+    }
+
+    /// Returned as a JS iterator, each song is numbered when JS asks for it.
+    #[neon_class(method)]
+    pub fn numbered(&self) -> impl Iterator<Item = String> {
+        let songs = self.songs.clone();
+        songs
+            .into_iter()
+            .enumerate()
+            .map(|(i, song)| format!("{}. {}", i + 1, song))
+    }
+}
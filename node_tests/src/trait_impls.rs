@@ -0,0 +1,46 @@
+use neon::prelude::{Context, Finalize};
+use neon_class_macros::neon_class;
+
+/// This struct is to test a class that can only be created with an `async_constructor`.
+#[derive(neon_class_macros::Class)]
+pub struct Device {
+    address: String,
+}
+
+impl Finalize for Device {}
+
+#[neon_class(impl_block, parts(endpoint))]
+impl Device {
+    #[neon_class(async_constructor, js_name = "open")]
+    pub async fn connect(address: String) -> Result<Self, String> {
+        if address.is_empty() {
+            return Err("Can't connect to an empty address".to_string());
+        }
+        Ok(Self { address })
+    }
+
+    #[neon_class(getter)]
+    pub fn address(&self) -> String {
+        self.address.clone()
+    }
+}
+
+/// Domain logic implemented as a trait, its methods are exported without wrappers.
+pub trait Endpoint {
+    fn protocol() -> String;
+    fn url(&self, path: String) -> String;
+}
+
+#[neon_class(impl_block, part = "endpoint")]
+impl Endpoint for Device {
+    #[neon_class(static_method)]
+    fn protocol() -> String {
+        "tcp".to_string()
+    }
+
+    /// The attribute can also be used through its crate.
+    #[neon_class_macros::neon_class(method)]
+    fn url(&self, path: String) -> String {
+        format!("{}://{}/{}", Self::protocol(), self.address, path)
+    }
+}
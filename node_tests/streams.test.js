@@ -0,0 +1,68 @@
+const mod = require("./index.node");
+const stream = require("stream");
+const { Writable } = stream;
+
+mod.registerStreams(stream);
+
+describe("Node streams", () => {
+  const collect = (highWaterMark) => {
+    const chunks = [];
+    const out = new Writable({
+      highWaterMark,
+      write(chunk, _encoding, callback) {
+        chunks.push(chunk.toString());
+        setImmediate(callback);
+      },
+    });
+    const finished = new Promise((resolve) => out.on("finish", resolve));
+    return { out, finished, text: () => chunks.join("") };
+  };
+
+  test("registerStreams only takes the stream module", () => {
+    expect(() => mod.registerStreams()).toThrow(TypeError);
+    expect(() => mod.registerStreams({})).toThrow(TypeError);
+  });
+
+  test("an impl Read is a Readable of Buffers", async () => {
+    const chunks = [];
+    for await (const chunk of mod.repeatByte(97, 100000)) {
+      expect(Buffer.isBuffer(chunk)).toBe(true);
+      chunks.push(chunk);
+    }
+    const data = Buffer.concat(chunks);
+    expect(data.length).toBe(100000);
+    expect(data.every((byte) => byte === 97)).toBe(true);
+  });
+
+  test("breaking out early destroys the Readable", async () => {
+    const readable = mod.repeatByte(98, 1 << 30);
+    for await (const chunk of readable) {
+      expect(chunk[0]).toBe(98);
+      break;
+    }
+    expect(readable.destroyed).toBe(true);
+  });
+
+  test("writes on the JS thread are queued", async () => {
+    const { out, finished, text } = collect();
+    mod.writeGreeting("rust", out);
+    await finished;
+    expect(text()).toBe("hello rust\n");
+  });
+
+  test("a blocking function waits for the Writable to drain", async () => {
+    const { out, finished, text } = collect(8);
+    await expect(mod.writeLines(3, out)).resolves.toBe(3);
+    await finished;
+    expect(text()).toBe("line 0\nline 1\nline 2\n");
+  });
+
+  test("a drain doesn't leave a 'close' listener behind", async () => {
+    const { out, finished } = collect(1);
+    const closeListeners = out.listenerCount("close");
+    await expect(mod.writeLines(4, out)).resolves.toBe(4);
+    expect(out.listenerCount("close")).toBe(closeListeners);
+    expect(out.listenerCount("drain")).toBe(0);
+    await finished;
+  });
+});
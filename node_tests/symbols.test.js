@@ -0,0 +1,45 @@
+const mod = require("./index.node");
+const util = require("util");
+
+describe("Playlist symbol methods", () => {
+  const playlist = () => new mod.Playlist(["intro", "outro"]);
+
+  test("Symbol.iterator works with for..of and spread", () => {
+    const songs = [];
+    for (const song of playlist()) {
+      songs.push(song);
+    }
+    expect(songs).toEqual(["intro", "outro"]);
+    expect([...playlist()]).toEqual(["intro", "outro"]);
+  });
+
+  test("Symbol.toPrimitive gets the hint", () => {
+    expect(+playlist()).toBe(2);
+    expect(`${playlist()}`).toBe("intro, outro");
+  });
+
+  test("util.inspect uses the inspect hook", () => {
+    expect(util.inspect(playlist())).toBe("Playlist(2 songs)");
+  });
+
+  test("Symbol.toStringTag is the class name", () => {
+    expect(Object.prototype.toString.call(playlist())).toBe(
+      "[object Playlist]"
+    );
+    expect(Object.prototype.toString.call(new mod.CsvReport())).toBe(
+      "[object CsvReport]"
+    );
+  });
+
+  test("an iterator is pulled lazily", () => {
+    const numbered = playlist().numbered();
+    expect(numbered.next()).toEqual({ done: false, value: "1. intro" });
+    expect([...numbered]).toEqual(["2. outro"]);
+    expect(numbered.next()).toEqual({ done: true, value: undefined });
+  });
+
+  test("symbol methods aren't set on their rust names", () => {
+    expect(playlist().songs).toBeUndefined();
+    expect(playlist().toPrimitive).toBeUndefined();
+  });
+});
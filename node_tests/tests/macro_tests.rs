@@ -15,3 +15,33 @@ fn check_rename_of_neon_class_macro_gives_error() {
     let t = trybuild::TestCases::new();
     t.compile_fail("./src/errors/rename_macro_error.rs");
 }
+
+#[test]
+fn mut_self_requires_mutable_impl_block() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("./src/errors/mut_self_without_mutable_error.rs");
+}
+
+#[test]
+fn mut_self_in_part_requires_mutable_impl_block() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("./src/errors/mut_self_in_part_error.rs");
+}
+
+#[test]
+fn async_self_requires_shared_impl_block() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("./src/errors/async_self_without_shared_error.rs");
+}
+
+#[test]
+fn invalid_neon_class_arg_gives_error() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("./src/errors/invalid_arg_error.rs");
+}
+
+#[test]
+fn invalid_impl_block_arg_gives_error() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("./src/errors/invalid_impl_block_arg_error.rs");
+}
@@ -0,0 +1,27 @@
+const mod = require("./index.node");
+
+describe("Device", () => {
+  test("can't be constructed with 'new' without a constructor", () => {
+    expect(() => new mod.Device("10.0.0.1")).toThrow(
+      "Device can't be constructed with 'new', use 'Device.open(...)' instead"
+    );
+  });
+
+  test("async constructor with js_name", async () => {
+    const device = await mod.Device.open("10.0.0.1");
+    expect(device.address).toBe("10.0.0.1");
+    expect(mod.Device.create).toBeUndefined();
+  });
+
+  test("async constructor rejects with the Err display", async () => {
+    await expect(mod.Device.open("")).rejects.toThrow(
+      "Can't connect to an empty address"
+    );
+  });
+
+  test("methods from a trait impl", async () => {
+    const device = await mod.Device.open("10.0.0.1");
+    expect(device.url("status")).toBe("tcp://10.0.0.1/status");
+    expect(mod.Device.protocol()).toBe("tcp");
+  });
+});
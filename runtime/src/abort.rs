@@ -0,0 +1,316 @@
+//! Cancellation of `async` and `blocking` methods from the JS side with an `AbortSignal`.
+//!
+//! A method that takes an [`AbortToken`] as its last arg can be given an `AbortSignal` (or an
+//! `{ signal }` options object) as its trailing arg on the JS side. When the signal is aborted the
+//! token is aborted and the promise returned by the method is rejected with an `AbortError`.
+use neon::prelude::{
+    Channel, Context, Deferred, Finalize, FunctionContext, Handle, JsBoolean, JsBox, JsError,
+    JsFunction, JsObject, JsResult, JsUndefined, JsValue, NeonResult, Object, Root,
+};
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::task::{Context as TaskContext, Poll, Waker};
+
+/// Cancellation token given to a method whose caller can abort it.
+///
+/// `async` methods are dropped at their next `.await` once aborted, `blocking` methods should check
+/// [`is_aborted`](AbortToken::is_aborted) to stop early.
+#[derive(Clone, Default)]
+pub struct AbortToken {
+    inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    aborted: AtomicBool,
+    wakers: Mutex<Vec<Waker>>,
+    callbacks: Mutex<Vec<Box<dyn FnOnce() + Send>>>,
+    listener: Mutex<Option<SignalListener>>,
+}
+
+/// The `abort` listener a token added to its `AbortSignal`.
+struct SignalListener {
+    signal: Root<JsObject>,
+    listener: Root<JsFunction>,
+}
+
+impl AbortToken {
+    /// A token that is only aborted by calling [`abort`](AbortToken::abort).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn abort(&self) {
+        self.inner.aborted.store(true, Ordering::SeqCst);
+        for waker in self.inner.wakers.lock().unwrap().drain(..) {
+            waker.wake();
+        }
+        let callbacks = std::mem::take(&mut *self.inner.callbacks.lock().unwrap());
+        for callback in callbacks {
+            callback();
+        }
+    }
+
+    /// Runs `f` once the token is aborted, right away if it already is.
+    pub fn on_abort<F: FnOnce() + Send + 'static>(&self, f: F) {
+        let mut callbacks = self.inner.callbacks.lock().unwrap();
+        // `abort` sets the flag before taking the callbacks, so `f` is either taken by it or run
+        // here.
+        if !self.is_aborted() {
+            callbacks.push(Box::new(f));
+            return;
+        }
+        drop(callbacks);
+        f();
+    }
+
+    pub fn is_aborted(&self) -> bool {
+        self.inner.aborted.load(Ordering::SeqCst)
+    }
+
+    /// Returns an [`AbortError`] if the token was aborted, handy with `?` in a `blocking` method.
+    pub fn check(&self) -> Result<(), AbortError> {
+        if self.is_aborted() {
+            Err(AbortError)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Completes once the token is aborted.
+    pub fn aborted(&self) -> Aborted<'_> {
+        Aborted { token: self }
+    }
+
+    /// Removes the listener the token added to its `AbortSignal`, once the call settled.
+    #[doc(hidden)]
+    pub fn release<'a, C: Context<'a>>(&self, cx: &mut C) -> NeonResult<()> {
+        let registered = self.inner.listener.lock().unwrap().take();
+        let SignalListener { signal, listener } = match registered {
+            Some(registered) => registered,
+            None => return Ok(()),
+        };
+        let signal = signal.into_inner(cx);
+        let listener = listener.into_inner(cx);
+
+        let remove_event_listener = signal
+            .get(cx, "removeEventListener")?
+            .downcast_or_throw::<JsFunction, _>(cx)?;
+        let event = cx.string("abort");
+        remove_event_listener.call(cx, signal, [event.upcast::<JsValue>(), listener.upcast()])?;
+        Ok(())
+    }
+}
+
+/// Future returned by [`AbortToken::aborted`].
+pub struct Aborted<'a> {
+    token: &'a AbortToken,
+}
+
+impl Future for Aborted<'_> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, task_cx: &mut TaskContext<'_>) -> Poll<()> {
+        if self.token.is_aborted() {
+            return Poll::Ready(());
+        }
+
+        let mut wakers = self.token.inner.wakers.lock().unwrap();
+        if !wakers.iter().any(|w| w.will_wake(task_cx.waker())) {
+            wakers.push(task_cx.waker().clone());
+        }
+        drop(wakers);
+
+        // `abort` might have run before the waker was registered.
+        if self.token.is_aborted() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+impl Finalize for AbortToken {}
+
+/// The promise of a `blocking` call that can be aborted.
+///
+/// It's rejected with an `AbortError` as soon as the token is aborted. The body can't be
+/// interrupted, what it returns after that is discarded.
+#[doc(hidden)]
+pub struct AbortableDeferred {
+    token: AbortToken,
+    deferred: Arc<Mutex<Option<Deferred>>>,
+}
+
+impl AbortableDeferred {
+    pub fn new(token: &AbortToken, channel: Channel, deferred: Deferred) -> Self {
+        let deferred = Arc::new(Mutex::new(Some(deferred)));
+        let aborted = Arc::clone(&deferred);
+        token.on_abort(move || {
+            if let Some(deferred) = aborted.lock().unwrap().take() {
+                channel.settle_with(deferred, |cx| throw_abort_error::<_, Handle<JsValue>>(cx));
+            }
+        });
+
+        Self {
+            token: token.clone(),
+            deferred,
+        }
+    }
+
+    /// Takes the deferred to settle it with the outcome of the call, `None` once aborted.
+    pub fn take(&self) -> Option<Deferred> {
+        let mut deferred = self.deferred.lock().unwrap();
+        // the abort callback may be about to take it.
+        if self.token.is_aborted() {
+            None
+        } else {
+            deferred.take()
+        }
+    }
+}
+
+/// Error for work that was cancelled through an [`AbortToken`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AbortError;
+
+impl fmt::Display for AbortError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("The operation was aborted")
+    }
+}
+
+impl std::error::Error for AbortError {}
+
+/// Runs `fut` until it completes or `token` is aborted, whichever happens first.
+pub fn abortable<F: Future>(token: &AbortToken, fut: F) -> Abortable<'_, F> {
+    Abortable {
+        aborted: token.aborted(),
+        fut: Box::pin(fut),
+    }
+}
+
+/// Future returned by [`abortable`].
+pub struct Abortable<'a, F> {
+    aborted: Aborted<'a>,
+    fut: Pin<Box<F>>,
+}
+
+impl<F: Future> Future for Abortable<'_, F> {
+    type Output = Result<F::Output, AbortError>;
+
+    fn poll(mut self: Pin<&mut Self>, task_cx: &mut TaskContext<'_>) -> Poll<Self::Output> {
+        if Pin::new(&mut self.aborted).poll(task_cx).is_ready() {
+            return Poll::Ready(Err(AbortError));
+        }
+        self.fut.as_mut().poll(task_cx).map(Ok)
+    }
+}
+
+/// Makes the token for the trailing `AbortSignal` (or `{ signal }` options object) of a call.
+///
+/// A missing arg, `undefined` or `null` gives a token that never gets aborted.
+#[doc(hidden)]
+pub fn from_signal<'a>(
+    cx: &mut FunctionContext<'a>,
+    arg: Option<Handle<'a, JsValue>>,
+) -> NeonResult<AbortToken> {
+    let token = AbortToken::new();
+
+    let arg = match arg.and_then(|arg| arg.downcast::<JsObject, _>(cx).ok()) {
+        Some(arg) => arg,
+        None => return Ok(token),
+    };
+
+    // an `AbortSignal` has an `aborted` flag, anything else is an options object.
+    let aborted = arg.get(cx, "aborted")?;
+    let signal = if aborted.is_a::<JsUndefined, _>(cx) {
+        match arg.get(cx, "signal")?.downcast::<JsObject, _>(cx) {
+            Ok(signal) => signal,
+            Err(_) => return Ok(token),
+        }
+    } else {
+        arg
+    };
+
+    let aborted = signal.get(cx, "aborted")?;
+    if let Ok(aborted) = aborted.downcast::<JsBoolean, _>(cx) {
+        if aborted.value(cx) {
+            token.abort();
+            return Ok(token);
+        }
+    }
+
+    // the listener gets the token as its first arg through `Function.prototype.bind`. It's a weak
+    // ref since the token keeps the listener rooted until the call settles.
+    let listener = JsFunction::new(cx, on_signal_abort)?;
+    let bind = listener
+        .get(cx, "bind")?
+        .downcast_or_throw::<JsFunction, _>(cx)?;
+    let null = cx.null();
+    let boxed_token = cx.boxed(WeakToken(Arc::downgrade(&token.inner)));
+    let listener = bind
+        .call(
+            cx,
+            listener,
+            [null.upcast::<JsValue>(), boxed_token.upcast()],
+        )?
+        .downcast_or_throw::<JsFunction, _>(cx)?;
+
+    let add_event_listener = signal
+        .get(cx, "addEventListener")?
+        .downcast_or_throw::<JsFunction, _>(cx)?;
+    let event = cx.string("abort");
+    let options = cx.empty_object();
+    let once = cx.boolean(true);
+    options.set(cx, "once", once)?;
+    add_event_listener.call(
+        cx,
+        signal,
+        [
+            event.upcast::<JsValue>(),
+            listener.upcast(),
+            options.upcast(),
+        ],
+    )?;
+
+    *token.inner.listener.lock().unwrap() = Some(SignalListener {
+        signal: signal.root(cx),
+        listener: listener.root(cx),
+    });
+
+    Ok(token)
+}
+
+/// The token given to the `abort` listener.
+struct WeakToken(Weak<Inner>);
+
+impl Finalize for WeakToken {}
+
+fn on_signal_abort(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+    let token = cx.argument::<JsBox<WeakToken>>(0)?;
+    if let Some(inner) = token.0.upgrade() {
+        AbortToken { inner }.abort();
+    }
+    Ok(cx.undefined())
+}
+
+/// Creates the `AbortError` a promise is rejected with, like the ones thrown by `fetch`.
+pub fn abort_error<'a, C: Context<'a>>(cx: &mut C) -> JsResult<'a, JsError> {
+    let error = cx.error(AbortError.to_string())?;
+    let name = cx.string("AbortError");
+    error.set(cx, "name", name)?;
+    let code = cx.string("ABORT_ERR");
+    error.set(cx, "code", code)?;
+    Ok(error)
+}
+
+/// Throws an [`abort_error`].
+#[doc(hidden)]
+pub fn throw_abort_error<'a, C: Context<'a>, T>(cx: &mut C) -> NeonResult<T> {
+    let error = abort_error(cx)?;
+    cx.throw(error)
+}
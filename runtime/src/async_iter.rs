@@ -0,0 +1,397 @@
+//! Support for methods that return a `Stream` or an `mpsc::Receiver`, which are turned into JS
+//! async iterators.
+//!
+//! One item is pulled from the rust source per call to `next()`, so nothing is pulled before JS
+//! asks for it. A `Stream` is polled by a task on the executor `async` methods run on, a
+//! `Receiver` can only be waited on by blocking so it gets its own thread. Calling `return()`,
+//! which is what `for await` does when the loop exits early, or dropping the JS object drops the
+//! source, even when a `next()` is still waiting on it.
+use neon::prelude::{
+    Channel, Context, Deferred, Finalize, FunctionContext, Handle, JsBox, JsFunction, JsObject,
+    JsPromise, JsResult, JsValue, Object, TaskContext,
+};
+use std::collections::VecDeque;
+use std::future::poll_fn;
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::task::{Poll, Waker};
+use std::time::Duration;
+
+pub use futures_core::Stream;
+
+/// Converts an item of the source to a JS value, i.e. with `neon_serde::to_value`.
+pub type ToJsFn<T> = for<'a> fn(&mut TaskContext<'a>, T) -> JsResult<'a, JsValue>;
+
+/// Key the async iterator state is stored under on the JS object.
+const STATE: &str = "__neon_async_iter";
+
+/// How often the thread waiting on an idle `Receiver` checks for `return()`.
+const RECV_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Something an async iterator can pull items from.
+pub trait Source: Send + 'static {
+    type Item: Send + 'static;
+
+    /// Starts pulling one item per pending `next()` of `requests` until they're closed.
+    fn start(self, requests: Arc<Requests>, to_js: ToJsFn<Self::Item>) -> io::Result<()>;
+}
+
+impl<T: Send + 'static> Source for Receiver<T> {
+    type Item = T;
+
+    fn start(self, requests: Arc<Requests>, to_js: ToJsFn<T>) -> io::Result<()> {
+        std::thread::Builder::new()
+            .name("neon-class-async-iter".into())
+            .spawn(move || drive_receiver(self, requests, to_js))?;
+        Ok(())
+    }
+}
+
+/// A [`Stream`] used as a [`Source`], it is polled by a task on the same executor `async` methods
+/// run on.
+pub struct FromStream<S>(Pin<Box<S>>);
+
+impl<S: Stream> FromStream<S> {
+    pub fn new(stream: S) -> Self {
+        Self(Box::pin(stream))
+    }
+}
+
+impl<S> Source for FromStream<S>
+where
+    S: Stream + Send + 'static,
+    S::Item: Send + 'static,
+{
+    type Item = S::Item;
+
+    fn start(self, requests: Arc<Requests>, to_js: ToJsFn<S::Item>) -> io::Result<()> {
+        crate::executor::spawn(move || drive_stream(self.0, requests, to_js));
+        Ok(())
+    }
+}
+
+/// A pending `next()`, the channel is created per call so only pending calls keep node alive.
+type Request = (Channel, Deferred);
+
+/// The pending `next()` calls of an async iterator, shared by its JS object and whatever pulls
+/// from its source.
+pub struct Requests {
+    state: Mutex<RequestsState>,
+    /// Wakes the thread of a `Receiver` when a `next()` is queued or the requests are closed.
+    changed: Condvar,
+}
+
+struct RequestsState {
+    pending: VecDeque<Request>,
+    /// Set by `return()`, once the source is done or when the JS object is collected.
+    closed: bool,
+    /// Wakes the task of a `Stream` when a `next()` is queued or the requests are closed.
+    waker: Option<Waker>,
+}
+
+impl Requests {
+    fn new() -> Self {
+        Self {
+            state: Mutex::new(RequestsState {
+                pending: VecDeque::new(),
+                closed: false,
+                waker: None,
+            }),
+            changed: Condvar::new(),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, RequestsState> {
+        // the state is only updated with single assignments, a panic can't leave it half updated.
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Queues a `next()`, it's given back once the requests are closed.
+    fn push(&self, request: Request) -> Result<(), Request> {
+        let mut state = self.state();
+        if state.closed {
+            return Err(request);
+        }
+        state.pending.push_back(request);
+        self.notify(state);
+        Ok(())
+    }
+
+    /// Stops pulling from the source, which is dropped as soon as whatever pulls from it notices.
+    fn close(&self) {
+        let mut state = self.state();
+        state.closed = true;
+        self.notify(state);
+    }
+
+    fn notify(&self, mut state: MutexGuard<'_, RequestsState>) {
+        let waker = state.waker.take();
+        drop(state);
+        self.changed.notify_all();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    fn is_closed(&self) -> bool {
+        self.state().closed
+    }
+
+    /// Blocks until a `next()` is queued, `None` once the requests are closed.
+    fn wait(&self) -> Option<Request> {
+        let mut state = self.state();
+        loop {
+            if state.closed {
+                return None;
+            }
+            if let Some(request) = state.pending.pop_front() {
+                return Some(request);
+            }
+            state = self
+                .changed
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// Like [`Requests::wait`] for a task.
+    fn poll_next(&self, cx: &mut std::task::Context<'_>) -> Poll<Option<Request>> {
+        let mut state = self.state();
+        if state.closed {
+            return Poll::Ready(None);
+        }
+        match state.pending.pop_front() {
+            Some(request) => Poll::Ready(Some(request)),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+
+    /// Ready once the requests are closed.
+    fn poll_closed(&self, cx: &mut std::task::Context<'_>) -> Poll<()> {
+        let mut state = self.state();
+        if state.closed {
+            return Poll::Ready(());
+        }
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+
+    /// Closes the requests and settles the pending ones as done, once the source was dropped.
+    fn finish(&self) {
+        let pending = {
+            let mut state = self.state();
+            state.closed = true;
+            std::mem::take(&mut state.pending)
+        };
+        for (channel, deferred) in pending {
+            settle_done(channel, deferred);
+        }
+    }
+}
+
+/// What a `next()` gets from the source.
+enum Pulled<T> {
+    Item(T),
+    /// The source is done or `return()` was called.
+    Done,
+    Panicked,
+}
+
+fn settle<T: Send + 'static>(
+    channel: Channel,
+    deferred: Deferred,
+    pulled: Pulled<T>,
+    to_js: ToJsFn<T>,
+) {
+    match pulled {
+        Pulled::Item(item) => channel.settle_with(deferred, move |cx| {
+            let value = to_js(cx, item)?;
+            iter_result(cx, false, value)
+        }),
+        Pulled::Done => settle_done(channel, deferred),
+        Pulled::Panicked => channel.settle_with(deferred, |cx| {
+            cx.throw_error::<_, Handle<JsValue>>("The source of the async iterator panicked")
+        }),
+    }
+}
+
+fn settle_done(channel: Channel, deferred: Deferred) {
+    channel.settle_with(deferred, |cx| {
+        let value = cx.undefined().upcast();
+        iter_result(cx, true, value)
+    });
+}
+
+/// Runs on the executor, polls the stream for each pending `next()` in order.
+async fn drive_stream<S>(mut stream: Pin<Box<S>>, requests: Arc<Requests>, to_js: ToJsFn<S::Item>)
+where
+    S: Stream,
+    S::Item: Send + 'static,
+{
+    let mut last = None;
+    while let Some((channel, deferred)) = poll_fn(|cx| requests.poll_next(cx)).await {
+        // `return()` wakes the task, so the stream is dropped even when it never yields again.
+        let pulled = poll_fn(|cx| {
+            if requests.poll_closed(cx).is_ready() {
+                return Poll::Ready(Pulled::Done);
+            }
+            match panic::catch_unwind(AssertUnwindSafe(|| stream.as_mut().poll_next(cx))) {
+                Ok(Poll::Ready(Some(item))) => Poll::Ready(Pulled::Item(item)),
+                Ok(Poll::Ready(None)) => Poll::Ready(Pulled::Done),
+                Ok(Poll::Pending) => Poll::Pending,
+                Err(_) => Poll::Ready(Pulled::Panicked),
+            }
+        })
+        .await;
+        match pulled {
+            Pulled::Item(item) => settle(channel, deferred, Pulled::Item(item), to_js),
+            pulled => {
+                last = Some((channel, deferred, pulled));
+                break;
+            }
+        }
+    }
+    // the source is gone by the time JS sees the iterator done.
+    drop(stream);
+    if let Some((channel, deferred, pulled)) = last {
+        settle(channel, deferred, pulled, to_js);
+    }
+    requests.finish();
+}
+
+/// Runs on the thread of a `Receiver`, receives an item for each pending `next()` in order.
+fn drive_receiver<T: Send + 'static>(
+    receiver: Receiver<T>,
+    requests: Arc<Requests>,
+    to_js: ToJsFn<T>,
+) {
+    let mut last = None;
+    while let Some((channel, deferred)) = requests.wait() {
+        // a `recv` can't be interrupted, so `return()` is checked for between waits.
+        let pulled = loop {
+            if requests.is_closed() {
+                break Pulled::Done;
+            }
+            match receiver.recv_timeout(RECV_INTERVAL) {
+                Ok(item) => break Pulled::Item(item),
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => break Pulled::Done,
+            }
+        };
+        match pulled {
+            Pulled::Item(item) => settle(channel, deferred, Pulled::Item(item), to_js),
+            pulled => {
+                last = Some((channel, deferred, pulled));
+                break;
+            }
+        }
+    }
+    // the source is gone by the time JS sees the iterator done.
+    drop(receiver);
+    if let Some((channel, deferred, pulled)) = last {
+        settle(channel, deferred, pulled, to_js);
+    }
+    requests.finish();
+}
+
+struct AsyncIterState {
+    requests: Arc<Requests>,
+}
+
+impl Finalize for AsyncIterState {}
+
+impl Drop for AsyncIterState {
+    // once JS lets go of the iterator, nothing can ask for its items anymore.
+    fn drop(&mut self) {
+        self.requests.close();
+    }
+}
+
+/// Wraps `source` in a JS async iterator object, its items are converted with `to_js` when JS
+/// asks for them.
+pub fn to_js_async_iterator<'a, C, S>(
+    cx: &mut C,
+    source: S,
+    to_js: ToJsFn<S::Item>,
+) -> JsResult<'a, JsObject>
+where
+    C: Context<'a>,
+    S: Source,
+{
+    let requests = Arc::new(Requests::new());
+    source
+        .start(Arc::clone(&requests), to_js)
+        .or_else(|e| cx.throw_error(format!("Failed to start the async iterator: {}", e)))?;
+
+    let obj = cx.empty_object();
+    let state = cx.boxed(AsyncIterState { requests });
+    obj.set(cx, STATE, state)?;
+    let next = JsFunction::new(cx, next)?;
+    obj.set(cx, "next", next)?;
+    let ret = JsFunction::new(cx, ret)?;
+    obj.set(cx, "return", ret)?;
+
+    // an async iterator is async iterable itself, this lets it be used with `for await`.
+    let async_iterator = cx
+        .global()
+        .get(cx, "Symbol")?
+        .downcast_or_throw::<JsObject, _>(cx)?
+        .get(cx, "asyncIterator")?;
+    let itself = JsFunction::new(cx, |mut cx| Ok(cx.this()))?;
+    obj.set(cx, async_iterator, itself)?;
+    Ok(obj)
+}
+
+fn iter_result<'a, C: Context<'a>>(
+    cx: &mut C,
+    done: bool,
+    value: Handle<'a, JsValue>,
+) -> JsResult<'a, JsObject> {
+    let result = cx.empty_object();
+    let done = cx.boolean(done);
+    result.set(cx, "done", done)?;
+    result.set(cx, "value", value)?;
+    Ok(result)
+}
+
+fn state<'a>(cx: &mut FunctionContext<'a>) -> JsResult<'a, JsBox<AsyncIterState>> {
+    cx.this()
+        .get(cx, STATE)?
+        .downcast_or_throw::<JsBox<AsyncIterState>, _>(cx)
+}
+
+/// The `next()` of the JS async iterator object.
+fn next(mut cx: FunctionContext) -> JsResult<JsPromise> {
+    let state = state(&mut cx)?;
+    let (deferred, promise) = cx.promise();
+    let request = (cx.channel(), deferred);
+
+    // after `return()`, or once the source is done, the iterator is done.
+    if let Err((_, deferred)) = state.requests.push(request) {
+        let value = cx.undefined().upcast();
+        let result = iter_result(&mut cx, true, value)?;
+        deferred.resolve(&mut cx, result);
+    }
+    Ok(promise)
+}
+
+/// The `return()` of the JS async iterator object, stops pulling from the source.
+fn ret(mut cx: FunctionContext) -> JsResult<JsPromise> {
+    let state = state(&mut cx)?;
+    state.requests.close();
+
+    let (deferred, promise) = cx.promise();
+    let value = match cx.argument_opt(0) {
+        Some(value) => value,
+        None => cx.undefined().upcast(),
+    };
+    let result = iter_result(&mut cx, true, value)?;
+    deferred.resolve(&mut cx, result);
+    Ok(promise)
+}
@@ -0,0 +1,82 @@
+//! Runs the bodies of `blocking` methods and functions off the JS main thread.
+//!
+//! By default they run on a small pool of threads owned by this crate. With the `tokio`
+//! feature they run on the blocking pool of the shared tokio runtime instead.
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/// Runs `f` on a worker thread.
+pub fn spawn_blocking<F>(f: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    imp::spawn_blocking(f)
+}
+
+/// Runs `f`, turning a panic into an `Err` with the panic's message so the promise of a
+/// `blocking` call is rejected instead of never being settled.
+#[doc(hidden)]
+pub fn catch_panic<T, F>(f: F) -> Result<T, String>
+where
+    F: FnOnce() -> Result<T, String>,
+{
+    catch_unwind(AssertUnwindSafe(f))
+        .unwrap_or_else(|payload| Err(crate::executor::panic_message(payload)))
+}
+
+#[cfg(not(feature = "tokio"))]
+mod imp {
+    use once_cell::sync::Lazy;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::sync::mpsc::{channel, Sender};
+    use std::sync::{Arc, Mutex};
+
+    /// Number of worker threads in the pool.
+    const POOL_SIZE: usize = 4;
+
+    type Job = Box<dyn FnOnce() + Send>;
+
+    /// Sends jobs to the workers, which are started the first time a job is sent.
+    static POOL: Lazy<Mutex<Sender<Job>>> = Lazy::new(|| {
+        let (sender, receiver) = channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        for i in 0..POOL_SIZE {
+            let receiver = Arc::clone(&receiver);
+            std::thread::Builder::new()
+                .name(format!("neon-class-blocking-{}", i))
+                .spawn(move || loop {
+                    let job = receiver.lock().unwrap().recv();
+                    match job {
+                        // a panicking job shouldn't take the worker down with it.
+                        Ok(job) => drop(catch_unwind(AssertUnwindSafe(job))),
+                        Err(_) => break,
+                    }
+                })
+                .expect("Failed to spawn a blocking worker thread");
+        }
+
+        Mutex::new(sender)
+    });
+
+    pub fn spawn_blocking<F>(f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        POOL.lock()
+            .unwrap()
+            .send(Box::new(f))
+            .expect("The blocking workers are gone");
+    }
+}
+
+#[cfg(feature = "tokio")]
+mod imp {
+    use crate::executor::RUNTIME;
+
+    pub fn spawn_blocking<F>(f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        RUNTIME.spawn_blocking(f);
+    }
+}
@@ -0,0 +1,169 @@
+//! Support for JS function args that are called from rust, i.e. an arg of a decorated method typed
+//! `ThreadsafeCallback<(String, u32)>` or `JsFn<'ctx, (u32, String), bool>`.
+//!
+//! The args of a call are given as a tuple, each item is converted via `neon_serde` and given to
+//! the JS function as a separate arg.
+use neon::prelude::{
+    Channel, Context, Handle, JsFunction, JsObject, JsString, JsValue, NeonResult, Object, Root,
+};
+use neon_serde::errors::MapErrIntoThrow;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// The args of a JS function call, implemented for tuples of up to 6 `Serialize` items.
+pub trait JsArgs {
+    fn to_js_args<'a, C: Context<'a>>(&self, cx: &mut C) -> NeonResult<Vec<Handle<'a, JsValue>>>;
+}
+
+macro_rules! impl_js_args {
+    ($($item:ident),*) => {
+        impl<$($item: Serialize),*> JsArgs for ($($item,)*) {
+            #[allow(non_snake_case, unused_variables)]
+            fn to_js_args<'a, Cx: Context<'a>>(
+                &self,
+                cx: &mut Cx,
+            ) -> NeonResult<Vec<Handle<'a, JsValue>>> {
+                let ($($item,)*) = self;
+                Ok(vec![$(neon_serde::to_value(cx, $item).map_err_into_throw(cx)?),*])
+            }
+        }
+    };
+}
+
+impl_js_args!();
+impl_js_args!(T1);
+impl_js_args!(T1, T2);
+impl_js_args!(T1, T2, T3);
+impl_js_args!(T1, T2, T3, T4);
+impl_js_args!(T1, T2, T3, T4, T5);
+impl_js_args!(T1, T2, T3, T4, T5, T6);
+
+/// The rooted function, it is unrooted on the JS thread once the last clone is dropped.
+struct Rooted {
+    channel: Channel,
+    callback: Option<Root<JsFunction>>,
+}
+
+impl Drop for Rooted {
+    fn drop(&mut self) {
+        if let Some(callback) = self.callback.take() {
+            self.channel.send(move |mut cx| {
+                callback.drop(&mut cx);
+                Ok(())
+            });
+        }
+    }
+}
+
+/// A JS function that can be called from any thread, with the args `T` converted via
+/// `neon_serde`.
+///
+/// It can be cloned and sent to other threads. The calls run on the JS thread, which is kept
+/// running until the last clone is dropped.
+pub struct ThreadsafeCallback<T> {
+    rooted: Arc<Rooted>,
+    args: PhantomData<fn(T)>,
+}
+
+impl<T> Clone for ThreadsafeCallback<T> {
+    fn clone(&self) -> Self {
+        Self {
+            rooted: Arc::clone(&self.rooted),
+            args: PhantomData,
+        }
+    }
+}
+
+impl<T: JsArgs + Send + 'static> ThreadsafeCallback<T> {
+    pub fn new<'a, C: Context<'a>>(cx: &mut C, callback: Handle<'a, JsFunction>) -> Self {
+        Self {
+            rooted: Arc::new(Rooted {
+                channel: cx.channel(),
+                callback: Some(callback.root(cx)),
+            }),
+            args: PhantomData,
+        }
+    }
+
+    /// Queues a call of the function with `args` on the JS thread.
+    pub fn call(&self, args: T) {
+        let rooted = Arc::clone(&self.rooted);
+        self.rooted.channel.send(move |mut cx| {
+            // only taken by `drop`, which can't run while this clone is alive.
+            let callback = match &rooted.callback {
+                Some(callback) => callback.to_inner(&mut cx),
+                None => return Ok(()),
+            };
+            let args = args.to_js_args(&mut cx)?;
+            let this = cx.undefined();
+            callback.call(&mut cx, this, args)?;
+            Ok(())
+        });
+    }
+}
+
+/// Error for a call of a [`JsFn`] that didn't return an `R`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CallError {
+    /// The function threw, or one of the args couldn't be converted, with the message of what
+    /// was thrown.
+    Threw(String),
+    /// The returned value couldn't be converted to `R`.
+    Return(String),
+}
+
+impl fmt::Display for CallError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CallError::Threw(message) => write!(f, "The JS function threw: {}", message),
+            CallError::Return(message) => {
+                write!(f, "The JS function returned an invalid value: {}", message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CallError {}
+
+/// A JS function that is called on the JS thread while the decorated method runs, with the args
+/// `A` and the return value `R` converted via `neon_serde`.
+pub struct JsFn<'ctx, A, R> {
+    callback: Handle<'ctx, JsFunction>,
+    types: PhantomData<fn(A) -> R>,
+}
+
+impl<'ctx, A: JsArgs, R: DeserializeOwned> JsFn<'ctx, A, R> {
+    pub fn new(callback: Handle<'ctx, JsFunction>) -> Self {
+        Self {
+            callback,
+            types: PhantomData,
+        }
+    }
+
+    /// Calls the function with `args`, a throw is caught and returned as an `Err`.
+    pub fn call<C: Context<'ctx>>(&self, cx: &mut C, args: A) -> Result<R, CallError> {
+        let returned = cx
+            .try_catch(|cx| {
+                let args = args.to_js_args(cx)?;
+                let this = cx.undefined();
+                self.callback.call(cx, this, args)
+            })
+            .map_err(|thrown| CallError::Threw(thrown_message(cx, thrown)))?;
+        neon_serde::from_value(cx, returned).map_err(|e| CallError::Return(e.to_string()))
+    }
+}
+
+/// The message of an `Error`, other thrown values are shown as they are if they're strings.
+fn thrown_message<'a, C: Context<'a>>(cx: &mut C, thrown: Handle<'a, JsValue>) -> String {
+    let message = match thrown.downcast::<JsObject, _>(cx) {
+        Ok(error) => error.get(cx, "message").ok(),
+        Err(_) => Some(thrown),
+    };
+    message
+        .and_then(|message| message.downcast::<JsString, _>(cx).ok())
+        .map(|message| message.value(cx))
+        .unwrap_or_else(|| "a value that isn't an Error or a string".to_string())
+}
@@ -0,0 +1,163 @@
+//! Support for `#[neon_class(impl_block, events)]`, which gives the instances of a class the
+//! `on`, `once`, `off` and `removeAllListeners` methods of a node `EventEmitter`.
+//!
+//! The listeners are kept by the [`EventSender`] of the instance, so the rust side can emit events
+//! from any thread. Each emit is serialized on the JS thread and given to the listeners through a
+//! [`Channel`].
+//!
+//! The listeners are rooted, so unlike those of an `EventEmitter` they aren't collected along with
+//! the instance: a listener that captures its own instance keeps both alive until it's removed
+//! with `off` or `removeAllListeners`.
+use neon::prelude::{Channel, Context, Handle, JsFunction, NeonResult, Object, Root};
+use neon_serde::errors::MapErrIntoThrow;
+use serde::Serialize;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+struct Listener {
+    event: String,
+    callback: Arc<Root<JsFunction>>,
+    once: bool,
+}
+
+#[derive(Default)]
+struct Listeners {
+    /// Set by the first listener, there's nothing to emit to before that.
+    channel: Option<Channel>,
+    listeners: Vec<Listener>,
+}
+
+/// Emits events to the JS listeners of an instance, it is the `events` field of a struct whose
+/// `impl_block` has the `events` arg, or the field named with `events = "..."`.
+///
+/// It can be cloned and sent to other threads, every clone emits to the same listeners.
+#[derive(Clone, Default)]
+pub struct EventSender(Arc<Mutex<Listeners>>);
+
+impl EventSender {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn listeners(&self) -> MutexGuard<'_, Listeners> {
+        // a panic can't leave the listeners half updated.
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Calls the listeners of `event` with `payload`, converted via `neon_serde`. Listeners added
+    /// with `once` are removed.
+    ///
+    /// Returns `true` if the event had listeners, like `EventEmitter.emit`.
+    pub fn emit<T>(&self, event: &str, payload: T) -> bool
+    where
+        T: Serialize + Send + 'static,
+    {
+        let mut listeners = self.listeners();
+        let mut callbacks = Vec::new();
+        listeners.listeners.retain(|listener| {
+            if listener.event != event {
+                return true;
+            }
+            callbacks.push(Arc::clone(&listener.callback));
+            !listener.once
+        });
+        let channel = match &listeners.channel {
+            Some(channel) if !callbacks.is_empty() => channel,
+            _ => return false,
+        };
+
+        channel.send(move |mut cx| {
+            let result = call_listeners(&mut cx, &callbacks, &payload);
+            // even when a listener threw, these may be the last clones of removed or `once`
+            // listeners.
+            for callback in callbacks {
+                unroot(&mut cx, callback);
+            }
+            result
+        });
+        true
+    }
+
+    /// Adds a listener, for the generated `on` and `once` methods.
+    #[doc(hidden)]
+    pub fn add_listener<'a, C: Context<'a>>(
+        &self,
+        cx: &mut C,
+        event: String,
+        callback: Handle<'a, JsFunction>,
+        once: bool,
+    ) {
+        let mut listeners = self.listeners();
+        if listeners.channel.is_none() {
+            // like an `EventEmitter`, listening doesn't keep node running.
+            let mut channel = cx.channel();
+            channel.unref(cx);
+            listeners.channel = Some(channel);
+        }
+        listeners.listeners.push(Listener {
+            event,
+            callback: Arc::new(callback.root(cx)),
+            once,
+        });
+    }
+
+    /// Removes the last matching listener, for the generated `off` method.
+    #[doc(hidden)]
+    pub fn remove_listener<'a, C: Context<'a>>(
+        &self,
+        cx: &mut C,
+        event: &str,
+        callback: Handle<'a, JsFunction>,
+    ) {
+        let mut listeners = self.listeners();
+        let idx = listeners.listeners.iter().rposition(|listener| {
+            listener.event == event && listener.callback.to_inner(cx).strict_equals(cx, callback)
+        });
+        if let Some(idx) = idx {
+            let listener = listeners.listeners.remove(idx);
+            unroot(cx, listener.callback);
+        }
+    }
+
+    /// Removes the listeners of `event`, or all of them without one, for the generated
+    /// `removeAllListeners` method.
+    #[doc(hidden)]
+    pub fn remove_all_listeners<'a, C: Context<'a>>(&self, cx: &mut C, event: Option<&str>) {
+        let mut listeners = self.listeners();
+        let (removed, kept) = std::mem::take(&mut listeners.listeners)
+            .into_iter()
+            .partition(|listener| match event {
+                Some(event) => listener.event == event,
+                None => true,
+            });
+        listeners.listeners = kept;
+        for listener in removed {
+            unroot(cx, listener.callback);
+        }
+    }
+}
+
+/// Calls each listener with `payload`, like an `EventEmitter` the ones after a listener that throws
+/// aren't called.
+fn call_listeners<'a, C, T>(
+    cx: &mut C,
+    callbacks: &[Arc<Root<JsFunction>>],
+    payload: &T,
+) -> NeonResult<()>
+where
+    C: Context<'a>,
+    T: Serialize,
+{
+    let payload = neon_serde::to_value(cx, payload).map_err_into_throw(cx)?;
+    for callback in callbacks {
+        let this = cx.undefined();
+        callback.to_inner(cx).call(cx, this, vec![payload])?;
+    }
+    Ok(())
+}
+
+/// Unroots a listener once the last clone of it is dropped, a pending emit may still hold one.
+fn unroot<'a, C: Context<'a>>(cx: &mut C, callback: Arc<Root<JsFunction>>) {
+    if let Ok(callback) = Arc::try_unwrap(callback) {
+        callback.drop(cx);
+    }
+}
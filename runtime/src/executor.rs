@@ -0,0 +1,235 @@
+//! Drives the futures returned by `async` methods and functions off the JS main thread.
+//!
+//! The futures are spread over a small pool of threads, each thread polls the futures it was given
+//! as they're woken so a pending future doesn't hold up its thread. By default the threads run a
+//! minimal built-in executor. With the `tokio` feature each of them drives a tokio `LocalSet` on a
+//! shared runtime instead, so the futures can use tokio's IO and timers.
+use std::any::Any;
+use std::future::Future;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::task::{Context, Poll};
+
+/// Number of threads the futures are spread over.
+const POOL_SIZE: usize = 4;
+
+/// The futures are given to the threads in turns.
+static NEXT_WORKER: AtomicUsize = AtomicUsize::new(0);
+
+/// The thread the next future goes to.
+fn next_worker() -> usize {
+    NEXT_WORKER.fetch_add(1, Ordering::Relaxed) % POOL_SIZE
+}
+
+/// The tokio runtime shared by the futures and the blocking tasks.
+#[cfg(feature = "tokio")]
+pub(crate) static RUNTIME: once_cell::sync::Lazy<tokio::runtime::Runtime> =
+    once_cell::sync::Lazy::new(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .thread_name("neon-class-runtime")
+            .build()
+            .expect("Failed to build the tokio runtime")
+    });
+
+/// Runs the future built by `make_future` to completion off the JS main thread.
+///
+/// The future itself is built on the thread that runs it, so it doesn't need to be `Send`, only
+/// whatever `make_future` captures does.
+pub fn spawn<F, Fut>(make_future: F)
+where
+    F: FnOnce() -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + 'static,
+{
+    imp::spawn(make_future)
+}
+
+/// Turns a panic of `fut` into an `Err` with the panic's message, so the promise of an `async`
+/// call is rejected instead of never being settled.
+#[doc(hidden)]
+pub fn catch_panic<T, Fut>(fut: Fut) -> CatchPanic<Fut>
+where
+    Fut: Future<Output = Result<T, String>>,
+{
+    CatchPanic(Box::pin(fut))
+}
+
+/// Future returned by [`catch_panic`].
+#[doc(hidden)]
+pub struct CatchPanic<Fut>(Pin<Box<Fut>>);
+
+impl<T, Fut> Future for CatchPanic<Fut>
+where
+    Fut: Future<Output = Result<T, String>>,
+{
+    type Output = Result<T, String>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let fut = self.0.as_mut();
+        match catch_unwind(AssertUnwindSafe(|| fut.poll(cx))) {
+            Ok(poll) => poll,
+            Err(payload) => Poll::Ready(Err(panic_message(payload))),
+        }
+    }
+}
+
+/// The error a call is rejected with when it panicked.
+pub(crate) fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str));
+    match message {
+        Some(message) => format!("The call panicked: {}", message),
+        None => "The call panicked".to_string(),
+    }
+}
+
+#[cfg(not(feature = "tokio"))]
+mod imp {
+    use super::{next_worker, POOL_SIZE};
+    use once_cell::sync::Lazy;
+    use std::collections::HashMap;
+    use std::future::Future;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::pin::Pin;
+    use std::sync::mpsc::{channel, Receiver, Sender};
+    use std::sync::{Arc, Mutex};
+    use std::task::{Context, Poll, Wake, Waker};
+
+    type LocalFuture = Pin<Box<dyn Future<Output = ()>>>;
+
+    enum Message {
+        /// Builds a new future, on the worker since it may not be `Send`.
+        Spawn(Box<dyn FnOnce() -> LocalFuture + Send>),
+        /// The future with this id was woken.
+        Wake(usize),
+    }
+
+    /// Sends messages to the workers, which are started the first time a future is spawned.
+    static WORKERS: Lazy<Vec<Mutex<Sender<Message>>>> = Lazy::new(|| {
+        (0..POOL_SIZE)
+            .map(|i| {
+                let (sender, receiver) = channel();
+                let wake_sender = sender.clone();
+                std::thread::Builder::new()
+                    .name(format!("neon-class-executor-{}", i))
+                    .spawn(move || run_worker(wake_sender, receiver))
+                    .expect("Failed to spawn an executor thread");
+                Mutex::new(sender)
+            })
+            .collect()
+    });
+
+    pub fn spawn<F, Fut>(make_future: F)
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + 'static,
+    {
+        let make_future = Box::new(move || Box::pin(make_future()) as LocalFuture);
+        WORKERS[next_worker()]
+            .lock()
+            .unwrap()
+            .send(Message::Spawn(make_future))
+            .expect("The executor threads are gone");
+    }
+
+    /// Polls the futures of a worker as they're spawned and woken.
+    fn run_worker(sender: Sender<Message>, receiver: Receiver<Message>) {
+        let mut futures = HashMap::<usize, LocalFuture>::new();
+        let mut next_id = 0;
+
+        for message in receiver {
+            let id = match message {
+                Message::Spawn(make_future) => {
+                    let id = next_id;
+                    next_id += 1;
+                    match catch_unwind(AssertUnwindSafe(make_future)) {
+                        Ok(fut) => futures.insert(id, fut),
+                        Err(_) => continue,
+                    };
+                    id
+                }
+                Message::Wake(id) => id,
+            };
+            // a future can still be woken after it completed.
+            let fut = match futures.get_mut(&id) {
+                Some(fut) => fut,
+                None => continue,
+            };
+
+            let waker = Waker::from(Arc::new(TaskWaker {
+                id,
+                worker: Mutex::new(sender.clone()),
+            }));
+            let mut cx = Context::from_waker(&waker);
+            // a panicking future shouldn't take the worker and its other futures down with it.
+            match catch_unwind(AssertUnwindSafe(|| fut.as_mut().poll(&mut cx))) {
+                Ok(Poll::Pending) => {}
+                Ok(Poll::Ready(())) | Err(_) => {
+                    futures.remove(&id);
+                }
+            }
+        }
+    }
+
+    /// Wakes up a future by queueing its id on the worker that owns it.
+    struct TaskWaker {
+        id: usize,
+        worker: Mutex<Sender<Message>>,
+    }
+
+    impl Wake for TaskWaker {
+        fn wake(self: Arc<Self>) {
+            // the worker only goes away with the process.
+            let _ = self.worker.lock().unwrap().send(Message::Wake(self.id));
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+mod imp {
+    use super::{next_worker, POOL_SIZE, RUNTIME};
+    use once_cell::sync::Lazy;
+    use std::future::Future;
+    use std::pin::Pin;
+    use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+    use tokio::task::LocalSet;
+
+    type MakeFuture = Box<dyn FnOnce() -> Pin<Box<dyn Future<Output = ()>>> + Send>;
+
+    /// Sends the futures to the workers, which are started the first time a future is spawned.
+    /// Each worker drives a `LocalSet` on the runtime, so the futures don't need to be `Send` and
+    /// still have access to the runtime's IO and timers.
+    static WORKERS: Lazy<Vec<UnboundedSender<MakeFuture>>> = Lazy::new(|| {
+        (0..POOL_SIZE)
+            .map(|i| {
+                let (sender, mut receiver) = unbounded_channel::<MakeFuture>();
+                std::thread::Builder::new()
+                    .name(format!("neon-class-executor-{}", i))
+                    .spawn(move || {
+                        LocalSet::new().block_on(&RUNTIME, async move {
+                            while let Some(make_future) = receiver.recv().await {
+                                // a panicking task doesn't take the worker down with it.
+                                tokio::task::spawn_local(async move { make_future().await });
+                            }
+                        })
+                    })
+                    .expect("Failed to spawn an executor thread");
+                sender
+            })
+            .collect()
+    });
+
+    pub fn spawn<F, Fut>(make_future: F)
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + 'static,
+    {
+        let make_future: MakeFuture = Box::new(move || Box::pin(make_future()));
+        if WORKERS[next_worker()].send(make_future).is_err() {
+            panic!("The executor threads are gone");
+        }
+    }
+}
@@ -0,0 +1,185 @@
+//! Support for classes declared with `#[neon_class(impl_block, extends = Base)]`.
+//!
+//! The prototype of a derived class inherits from the prototype of its base, so the methods of the
+//! base get called with an instance of the derived class as `this`. The base can't get to its own
+//! struct from there, so the derived prototype holds an [`Upcaster`] that borrows the base out of
+//! the derived struct with `AsRef`/`AsMut`.
+use neon::prelude::{Finalize, FunctionContext, Handle, JsBox, JsObject, NeonResult};
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+/// Implemented for the structs of `#[neon_class(impl_block, extendable)]` blocks.
+pub trait Extendable: Sized + 'static {
+    /// Name the class is exported with, used to find its prototype when registering a derived
+    /// class.
+    const JS_NAME: &'static str;
+    /// Key the boxed struct is stored under on the JS `this`.
+    const THIS: &'static str;
+}
+
+/// Borrows the `B` part of an instance of a derived class.
+pub trait Upcast<B> {
+    fn borrow(&self) -> Result<Box<dyn Deref<Target = B> + '_>, String>;
+    fn borrow_mut(&self) -> Result<Box<dyn DerefMut<Target = B> + '_>, String>;
+}
+
+/// Makes the [`Upcast`] for `obj`, an instance of a class derived from `B`.
+pub type UpcastFn<B> = for<'a> fn(
+    &mut FunctionContext<'a>,
+    Handle<'a, JsObject>,
+) -> NeonResult<Box<dyn Upcast<B> + 'a>>;
+
+/// Borrows the base `B` out of the `this` of a derived class.
+pub type BorrowFn<T, B> = for<'b> fn(&'b T) -> Result<Box<dyn Deref<Target = B> + 'b>, String>;
+
+/// Mutably borrows the base `B` out of the `this` of a derived class.
+pub type BorrowMutFn<T, B> =
+    for<'b> fn(&'b T) -> Result<Box<dyn DerefMut<Target = B> + 'b>, String>;
+
+/// Stored on the prototype of a derived class under the `THIS` key of its base.
+pub struct Upcaster<B>(pub UpcastFn<B>);
+
+impl<B: 'static> Finalize for Upcaster<B> {}
+
+/// An [`Upcast`] made from the `this` of a derived class and the functions that borrow the base
+/// out of it.
+pub struct Upcasted<T, B> {
+    this: T,
+    borrow: BorrowFn<T, B>,
+    borrow_mut: BorrowMutFn<T, B>,
+}
+
+impl<T, B> Upcasted<T, B> {
+    pub fn new(this: T, borrow: BorrowFn<T, B>, borrow_mut: BorrowMutFn<T, B>) -> Self {
+        Self {
+            this,
+            borrow,
+            borrow_mut,
+        }
+    }
+}
+
+impl<T, B> Upcast<B> for Upcasted<T, B> {
+    fn borrow(&self) -> Result<Box<dyn Deref<Target = B> + '_>, String> {
+        (self.borrow)(&self.this)
+    }
+
+    fn borrow_mut(&self) -> Result<Box<dyn DerefMut<Target = B> + '_>, String> {
+        (self.borrow_mut)(&self.this)
+    }
+}
+
+/// The `this` of a method of an extendable class.
+pub enum This<'a, T: Finalize + 'static, B> {
+    /// An instance of the class itself.
+    Own(Handle<'a, JsBox<T>>),
+    /// An instance of a derived class.
+    Derived(Box<dyn Upcast<B> + 'a>),
+}
+
+/// `&B` borrowed from a [`This`].
+pub enum Borrowed<'b, G, B> {
+    Own(G),
+    Derived(Box<dyn Deref<Target = B> + 'b>),
+}
+
+impl<G: Deref<Target = B>, B> Deref for Borrowed<'_, G, B> {
+    type Target = B;
+
+    fn deref(&self) -> &B {
+        match self {
+            Borrowed::Own(guard) => guard,
+            Borrowed::Derived(guard) => guard,
+        }
+    }
+}
+
+/// `&mut B` borrowed from a [`This`].
+pub enum BorrowedMut<'b, G, B> {
+    Own(G),
+    Derived(Box<dyn DerefMut<Target = B> + 'b>),
+}
+
+impl<G: Deref<Target = B>, B> Deref for BorrowedMut<'_, G, B> {
+    type Target = B;
+
+    fn deref(&self) -> &B {
+        match self {
+            BorrowedMut::Own(guard) => guard,
+            BorrowedMut::Derived(guard) => guard,
+        }
+    }
+}
+
+impl<G: DerefMut<Target = B>, B> DerefMut for BorrowedMut<'_, G, B> {
+    fn deref_mut(&mut self) -> &mut B {
+        match self {
+            BorrowedMut::Own(guard) => guard,
+            BorrowedMut::Derived(guard) => guard,
+        }
+    }
+}
+
+/// Turns a guard of a derived struct into a guard of its base with `AsRef`.
+pub struct MapRef<G, B> {
+    guard: G,
+    base: PhantomData<fn() -> B>,
+}
+
+impl<G, B> MapRef<G, B> {
+    pub fn new(guard: G) -> Self {
+        Self {
+            guard,
+            base: PhantomData,
+        }
+    }
+}
+
+impl<G, B> Deref for MapRef<G, B>
+where
+    G: Deref,
+    G::Target: AsRef<B>,
+{
+    type Target = B;
+
+    fn deref(&self) -> &B {
+        self.guard.deref().as_ref()
+    }
+}
+
+/// Turns a mutable guard of a derived struct into a guard of its base with `AsRef` and `AsMut`.
+pub struct MapMut<G, B> {
+    guard: G,
+    base: PhantomData<fn() -> B>,
+}
+
+impl<G, B> MapMut<G, B> {
+    pub fn new(guard: G) -> Self {
+        Self {
+            guard,
+            base: PhantomData,
+        }
+    }
+}
+
+impl<G, B> Deref for MapMut<G, B>
+where
+    G: Deref,
+    G::Target: AsRef<B>,
+{
+    type Target = B;
+
+    fn deref(&self) -> &B {
+        self.guard.deref().as_ref()
+    }
+}
+
+impl<G, B> DerefMut for MapMut<G, B>
+where
+    G: DerefMut,
+    G::Target: AsRef<B> + AsMut<B>,
+{
+    fn deref_mut(&mut self) -> &mut B {
+        self.guard.deref_mut().as_mut()
+    }
+}
@@ -0,0 +1,72 @@
+//! Support for methods that return an `Iterator`, which are turned into lazy JS iterators.
+//!
+//! The rust iterator is boxed on the JS iterator object, so it lives as long as JS holds the
+//! object. Each call to `next()` pulls one item from it and converts it with the function given
+//! by the generated glue.
+use neon::prelude::{
+    Context, Finalize, FunctionContext, JsBox, JsFunction, JsObject, JsResult, JsValue, Object,
+};
+use std::cell::RefCell;
+
+/// Converts an item of the iterator to a JS value, i.e. with `neon_serde::to_value`.
+pub type ToJsFn<T> = for<'a> fn(&mut FunctionContext<'a>, T) -> JsResult<'a, JsValue>;
+
+/// Key the iterator state is stored under on the JS iterator object.
+const STATE: &str = "__neon_iter";
+
+struct IterState<I: Iterator> {
+    iter: RefCell<I>,
+    to_js: ToJsFn<I::Item>,
+}
+
+impl<I: Iterator> Finalize for IterState<I> {}
+
+/// Wraps `iter` in a JS iterator object, its items are converted with `to_js` when JS asks for
+/// them.
+pub fn to_js_iterator<'a, C, I>(
+    cx: &mut C,
+    iter: I,
+    to_js: ToJsFn<I::Item>,
+) -> JsResult<'a, JsObject>
+where
+    C: Context<'a>,
+    I: Iterator + 'static,
+{
+    let obj = cx.empty_object();
+    let state = cx.boxed(IterState {
+        iter: RefCell::new(iter),
+        to_js,
+    });
+    obj.set(cx, STATE, state)?;
+    let next = JsFunction::new(cx, next::<I>)?;
+    obj.set(cx, "next", next)?;
+
+    // an iterator is iterable itself, this lets it be used with `for..of` and spread.
+    let iterator = cx
+        .global()
+        .get(cx, "Symbol")?
+        .downcast_or_throw::<JsObject, _>(cx)?
+        .get(cx, "iterator")?;
+    let itself = JsFunction::new(cx, |mut cx| Ok(cx.this()))?;
+    obj.set(cx, iterator, itself)?;
+    Ok(obj)
+}
+
+/// The `next()` of the JS iterator object.
+fn next<I: Iterator + 'static>(mut cx: FunctionContext) -> JsResult<JsObject> {
+    let state = cx
+        .this()
+        .get(&mut cx, STATE)?
+        .downcast_or_throw::<JsBox<IterState<I>>, _>(&mut cx)?;
+    let item = state.iter.borrow_mut().next();
+
+    let result = cx.empty_object();
+    let done = cx.boolean(item.is_none());
+    result.set(&mut cx, "done", done)?;
+    let value = match item {
+        Some(item) => (state.to_js)(&mut cx, item)?,
+        None => cx.undefined().upcast(),
+    };
+    result.set(&mut cx, "value", value)?;
+    Ok(result)
+}
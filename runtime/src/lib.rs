@@ -0,0 +1,22 @@
+//! Runtime support for the code generated by the `neon_class_macros` crate.
+//!
+//! Since `neon_class_macros` is a `proc-macro` crate it can only export macros, so everything the
+//! generated code needs at runtime lives here. Add this crate to your deps when using any of the
+//! features that need it (i.e. `async` or `blocking` methods, methods returning iterators, streams
+//! or channels, readers and writers, `events`, callbacks, `extends` or `remote`).
+pub mod abort;
+pub mod async_iter;
+pub mod blocking;
+pub mod callback;
+pub mod events;
+pub mod executor;
+pub mod inherit;
+pub mod iter;
+pub mod remote;
+pub mod stream;
+pub mod sync;
+
+pub use abort::{AbortError, AbortToken};
+pub use blocking::spawn_blocking;
+pub use callback::{CallError, JsFn, ThreadsafeCallback};
+pub use executor::spawn;
@@ -0,0 +1,25 @@
+//! Support for classes declared with `#[neon_class(impl_block, remote = some::Type)]`.
+//!
+//! A type from another crate can't implement [`Finalize`], so the generated glue boxes it in a
+//! [`Remote`] instead. The declared methods are called on the boxed value through `Deref`.
+use neon::prelude::Finalize;
+use std::ops::{Deref, DerefMut};
+
+/// Holds the foreign type of a `remote` impl_block inside its `JsBox`.
+pub struct Remote<T>(pub T);
+
+impl<T> Finalize for Remote<T> {}
+
+impl<T> Deref for Remote<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Remote<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
@@ -0,0 +1,273 @@
+//! Adapters between `std::io` and node streams, for methods that return an `impl Read` or take an
+//! `impl Write`.
+//!
+//! The `Readable`s are made with the `stream` module the JS side gives to `registerStreams`, see
+//! [`register_streams`].
+//!
+//! A reader is moved to its own thread, which reads one chunk each time the `Readable` asks for
+//! more data and pushes it to JS through a [`Channel`]. A [`JsWriter`] sends its chunks to the
+//! `Writable` through a [`Channel`] as well.
+use neon::prelude::{
+    Channel, Context, FunctionContext, Handle, JsBoolean, JsBuffer, JsError, JsFunction, JsNumber,
+    JsObject, JsResult, JsUndefined, JsValue, ModuleContext, NeonResult, Object, Root,
+};
+use std::cell::RefCell;
+use std::io::{self, Read, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::rc::Rc;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread::{self, ThreadId};
+
+/// Size of the chunks read when the `Readable` doesn't give one.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Events a write from another thread waits for when the `Writable`'s buffer is full.
+const WAIT_EVENTS: [&str; 2] = ["drain", "close"];
+
+thread_local! {
+    /// The `stream` module given to `registerStreams`, per JS thread since each worker has its
+    /// own.
+    static STREAM_MODULE: RefCell<Option<Root<JsObject>>> = const { RefCell::new(None) };
+}
+
+/// Exports `registerStreams`, which the JS side calls once with node's `stream` module, i.e.
+/// `registerStreams(require("stream"))`, before calling the methods returning an `impl Read`.
+///
+/// Native modules don't get a `require`, so the `Readable` class has to come from JS.
+pub fn register_streams(cx: &mut ModuleContext) -> NeonResult<()> {
+    cx.export_function("registerStreams", set_stream_module)
+}
+
+fn set_stream_module(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+    let module = cx
+        .argument_opt(0)
+        .and_then(|module| module.downcast::<JsObject, _>(&mut cx).ok());
+    let module = match module {
+        Some(module) if module.get(&mut cx, "Readable")?.is_a::<JsFunction, _>(&mut cx) => module,
+        _ => {
+            return cx.throw_type_error(
+                "registerStreams expects node's 'stream' module, i.e. registerStreams(require(\"stream\"))",
+            )
+        }
+    };
+    let module = module.root(&mut cx);
+    if let Some(previous) = STREAM_MODULE.with(|stream| stream.borrow_mut().replace(module)) {
+        previous.drop(&mut cx);
+    }
+    Ok(cx.undefined())
+}
+
+/// The `Readable` class of the module given to `registerStreams`.
+fn readable_class<'a, C: Context<'a>>(cx: &mut C) -> JsResult<'a, JsFunction> {
+    let module = STREAM_MODULE.with(|stream| stream.borrow().as_ref().map(|m| m.to_inner(cx)));
+    match module {
+        Some(module) => module.get(cx, "Readable")?.downcast_or_throw(cx),
+        None => cx.throw_error(
+            "The 'stream' module isn't registered, call registerStreams(require(\"stream\")) once \
+             after loading the native module.",
+        ),
+    }
+}
+
+fn call_method<'a, C: Context<'a>>(
+    cx: &mut C,
+    obj: Handle<'a, JsObject>,
+    name: &str,
+    args: Vec<Handle<'a, JsValue>>,
+) -> JsResult<'a, JsValue> {
+    obj.get(cx, name)?
+        .downcast_or_throw::<JsFunction, _>(cx)?
+        .call(cx, obj, args)
+}
+
+/// A call to `_read()`, the stream is rooted so the reader thread can push to it.
+struct ReadRequest {
+    channel: Channel,
+    stream: Root<JsObject>,
+    size: usize,
+}
+
+/// Wraps `reader` in a node `Readable` of `Buffer` chunks.
+pub fn to_readable<'a, C, R>(cx: &mut C, reader: R) -> JsResult<'a, JsObject>
+where
+    C: Context<'a>,
+    R: Read + Send + 'static,
+{
+    let readable = readable_class(cx)?;
+    let (requests, pending) = mpsc::channel();
+    thread::Builder::new()
+        .name("neon-class-readable".into())
+        .spawn(move || read_chunks(reader, pending))
+        .or_else(|e| cx.throw_error(format!("Failed to spawn the reader: {}", e)))?;
+
+    // dropped once the stream is destroyed, which stops the reader thread.
+    let requests = Rc::new(RefCell::new(Some(requests)));
+    let read_requests = Rc::clone(&requests);
+    let read = JsFunction::new(cx, move |mut cx| {
+        let stream = cx.this();
+        let size = match cx.argument_opt(0) {
+            Some(size) => match size.downcast::<JsNumber, _>(&mut cx) {
+                Ok(size) => size.value(&mut cx) as usize,
+                Err(_) => CHUNK_SIZE,
+            },
+            None => CHUNK_SIZE,
+        };
+        let request = ReadRequest {
+            channel: cx.channel(),
+            stream: stream.root(&mut cx),
+            size,
+        };
+        if let Some(requests) = &*read_requests.borrow() {
+            if let Err(unsent) = requests.send(request) {
+                unsent.0.stream.drop(&mut cx);
+            }
+        }
+        Ok(cx.undefined())
+    })?;
+    let destroy = JsFunction::new(cx, move |mut cx| {
+        requests.borrow_mut().take();
+        let err = cx.argument::<JsValue>(0)?;
+        let callback = cx.argument::<JsFunction>(1)?;
+        let this = cx.this();
+        callback.call(&mut cx, this, vec![err])?;
+        Ok(cx.undefined())
+    })?;
+
+    let options = cx.empty_object();
+    options.set(cx, "read", read)?;
+    options.set(cx, "destroy", destroy)?;
+    readable.construct(cx, vec![options])
+}
+
+/// Runs on the reader thread, reads one chunk per request until the end of the reader.
+fn read_chunks<R: Read>(mut reader: R, pending: Receiver<ReadRequest>) {
+    for ReadRequest {
+        channel,
+        stream,
+        size,
+    } in pending
+    {
+        let mut chunk = vec![0; size];
+        let read = panic::catch_unwind(AssertUnwindSafe(|| loop {
+            match reader.read(&mut chunk) {
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                read => break read,
+            }
+        }));
+        let end = !matches!(read, Ok(Ok(n)) if n > 0);
+
+        channel.send(move |mut cx| {
+            let stream = stream.into_inner(&mut cx);
+            let (method, value) = match read {
+                Ok(Ok(0)) => ("push", cx.null().upcast()),
+                Ok(Ok(n)) => {
+                    chunk.truncate(n);
+                    ("push", JsBuffer::external(&mut cx, chunk).upcast())
+                }
+                Ok(Err(e)) => ("destroy", JsError::error(&mut cx, e.to_string())?.upcast()),
+                Err(_) => (
+                    "destroy",
+                    JsError::error(&mut cx, "The reader panicked")?.upcast(),
+                ),
+            };
+            call_method(&mut cx, stream, method, vec![value])?;
+            Ok(())
+        });
+
+        if end {
+            break;
+        }
+    }
+}
+
+/// A `Write` over a node `Writable`, it is what an `impl Write` arg of a decorated method gets.
+///
+/// Writes made on the JS thread are queued and reach the stream after the method returns. Writes
+/// made on other threads, i.e. in `blocking` methods, wait for the stream to take the chunk and,
+/// when its buffer is full, for its `'drain'`. The stream is ended once the writer is dropped.
+pub struct JsWriter {
+    channel: Channel,
+    stream: Arc<Root<JsObject>>,
+    js_thread: ThreadId,
+}
+
+impl JsWriter {
+    pub fn new<'a, C: Context<'a>>(cx: &mut C, stream: Handle<'a, JsObject>) -> Self {
+        Self {
+            channel: cx.channel(),
+            stream: Arc::new(stream.root(cx)),
+            js_thread: thread::current().id(),
+        }
+    }
+}
+
+impl Write for JsWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let chunk = buf.to_vec();
+        let stream = Arc::clone(&self.stream);
+        // the JS thread can't wait on itself, its writes are only queued.
+        let wait = thread::current().id() != self.js_thread;
+        let (taken, on_taken) = mpsc::sync_channel(1);
+
+        self.channel.send(move |mut cx| {
+            let stream = stream.to_inner(&mut cx);
+            let buffer = JsBuffer::external(&mut cx, chunk).upcast();
+            let has_room = call_method(&mut cx, stream, "write", vec![buffer])?
+                .downcast::<JsBoolean, _>(&mut cx)
+                .map(|has_room| has_room.value(&mut cx))
+                .unwrap_or(true);
+
+            if has_room || !wait {
+                let _ = taken.try_send(());
+            } else {
+                // waits for a drain, or for a close since a closed stream never drains. Whichever
+                // comes first removes the listener of the other, which would pile up otherwise.
+                let listener = Rc::new(RefCell::new(None::<Root<JsFunction>>));
+                let resume_listener = Rc::clone(&listener);
+                let resume = JsFunction::new(&mut cx, move |mut cx| {
+                    let resume = resume_listener.borrow_mut().take();
+                    if let Some(resume) = resume {
+                        let stream = cx.this();
+                        let resume = resume.into_inner(&mut cx).upcast();
+                        for event in WAIT_EVENTS {
+                            let event = cx.string(event).upcast();
+                            call_method(&mut cx, stream, "removeListener", vec![event, resume])?;
+                        }
+                    }
+                    let _ = taken.try_send(());
+                    Ok(cx.undefined())
+                })?;
+                *listener.borrow_mut() = Some(resume.root(&mut cx));
+                let resume = resume.upcast();
+                for event in WAIT_EVENTS {
+                    let event = cx.string(event).upcast();
+                    call_method(&mut cx, stream, "once", vec![event, resume])?;
+                }
+            }
+            Ok(())
+        });
+
+        if wait {
+            on_taken.recv().map_err(|_| {
+                io::Error::new(io::ErrorKind::BrokenPipe, "The Writable threw on write")
+            })?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for JsWriter {
+    fn drop(&mut self) {
+        let stream = Arc::clone(&self.stream);
+        self.channel.send(move |mut cx| {
+            let stream = stream.to_inner(&mut cx);
+            call_method(&mut cx, stream, "end", vec![])?;
+            Ok(())
+        });
+    }
+}
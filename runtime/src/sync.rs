@@ -0,0 +1,136 @@
+//! The lock the instances of a `#[neon_class(impl_block, mutable = "Mutex")]` class are kept in.
+//!
+//! It is a [`std::sync::Mutex`] that `async` methods can also wait on without blocking their
+//! executor thread: a future that finds it locked registers its waker and is woken once the guard
+//! holding the lock is dropped.
+use neon::prelude::{Context as JsContext, Finalize};
+use std::future::Future;
+use std::ops::{Deref, DerefMut};
+use std::pin::Pin;
+use std::sync::{self, LockResult, PoisonError, TryLockError, TryLockResult};
+use std::task::{Context, Poll, Waker};
+
+pub struct Mutex<T> {
+    inner: sync::Mutex<T>,
+    /// The futures waiting for the lock, all of them are woken each time it's released.
+    waiters: sync::Mutex<Vec<Waker>>,
+}
+
+impl<T> Mutex<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            inner: sync::Mutex::new(value),
+            waiters: sync::Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Takes the lock if it's free, like [`std::sync::Mutex::try_lock`].
+    pub fn try_lock(&self) -> TryLockResult<MutexGuard<'_, T>> {
+        match self.inner.try_lock() {
+            Ok(guard) => Ok(self.guard(guard)),
+            Err(TryLockError::Poisoned(e)) => Err(TryLockError::Poisoned(self.poisoned(e))),
+            Err(TryLockError::WouldBlock) => Err(TryLockError::WouldBlock),
+        }
+    }
+
+    /// Blocks the thread until the lock is taken, like [`std::sync::Mutex::lock`].
+    pub fn lock(&self) -> LockResult<MutexGuard<'_, T>> {
+        self.inner
+            .lock()
+            .map(|guard| self.guard(guard))
+            .map_err(|e| self.poisoned(e))
+    }
+
+    /// Waits for the lock without blocking the thread.
+    pub fn lock_async(&self) -> Lock<'_, T> {
+        Lock(self)
+    }
+
+    fn guard<'a>(&'a self, guard: sync::MutexGuard<'a, T>) -> MutexGuard<'a, T> {
+        MutexGuard {
+            mutex: self,
+            guard: Some(guard),
+        }
+    }
+
+    fn poisoned<'a>(
+        &'a self,
+        e: PoisonError<sync::MutexGuard<'a, T>>,
+    ) -> PoisonError<MutexGuard<'a, T>> {
+        PoisonError::new(self.guard(e.into_inner()))
+    }
+
+    fn waiters(&self) -> sync::MutexGuard<'_, Vec<Waker>> {
+        // the wakers are only pushed and drained, a panic can't leave them half updated.
+        self.waiters.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<T: Finalize> Finalize for Mutex<T> {
+    fn finalize<'a, C: JsContext<'a>>(self, cx: &mut C) {
+        // like the `Finalize` of `std::sync::Mutex`, a poisoned value isn't finalized.
+        if let Ok(value) = self.inner.into_inner() {
+            value.finalize(cx);
+        }
+    }
+}
+
+/// Holds the lock of a [`Mutex`], the futures waiting for it are woken when it's dropped.
+pub struct MutexGuard<'a, T> {
+    mutex: &'a Mutex<T>,
+    /// Only taken by `drop`.
+    guard: Option<sync::MutexGuard<'a, T>>,
+}
+
+impl<T> Deref for MutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.guard.as_ref().unwrap()
+    }
+}
+
+impl<T> DerefMut for MutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.guard.as_mut().unwrap()
+    }
+}
+
+impl<T> Drop for MutexGuard<'_, T> {
+    fn drop(&mut self) {
+        // unlocks before waking, so the woken futures can take the lock.
+        self.guard.take();
+        let waiters = std::mem::take(&mut *self.mutex.waiters());
+        for waiter in waiters {
+            waiter.wake();
+        }
+    }
+}
+
+/// Future returned by [`Mutex::lock_async`].
+pub struct Lock<'a, T>(&'a Mutex<T>);
+
+impl<'a, T> Future for Lock<'a, T> {
+    type Output = LockResult<MutexGuard<'a, T>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mutex: &'a Mutex<T> = self.0;
+        let locked = match mutex.try_lock() {
+            Err(TryLockError::WouldBlock) => {
+                let mut waiters = mutex.waiters();
+                if !waiters.iter().any(|waiter| waiter.will_wake(cx.waker())) {
+                    waiters.push(cx.waker().clone());
+                }
+                drop(waiters);
+                // the lock may have been released before the waker was registered.
+                mutex.try_lock()
+            }
+            locked => locked,
+        };
+        match locked {
+            Ok(guard) => Poll::Ready(Ok(guard)),
+            Err(TryLockError::Poisoned(e)) => Poll::Ready(Err(e)),
+            Err(TryLockError::WouldBlock) => Poll::Pending,
+        }
+    }
+}
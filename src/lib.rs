@@ -24,16 +24,19 @@
 #![doc = include_str!("../node_tests/derivedClass.test.js")]
 //! ```
 //!
-use crate::utils::{AnnotatedFn, ImplTree, NeonMacrosAttrs};
-use heck::MixedCase;
+use crate::utils::{
+    AnnotatedFn, BoxedKind, ExportedConst, ImplBlockArgs, ImplTree, JsNaming, NeonMacrosAttrs,
+    RenameRule, SymbolKey,
+};
+use heck::SnakeCase;
 use proc_macro::TokenStream;
 use proc_macro2::Literal;
-use quote::{format_ident, quote};
+use quote::{format_ident, quote, ToTokens};
 use std::str::FromStr;
 use syn::spanned::Spanned;
 use syn::{
-    parse_macro_input, AttributeArgs, DeriveInput, ImplItem, ImplItemConst, ImplItemMethod, ItemFn,
-    ItemImpl, Lifetime, Meta, NestedMeta, Type,
+    parse_macro_input, AttributeArgs, DeriveInput, FnArg, ImplItem, ImplItemConst, ImplItemMethod,
+    ItemFn, ItemImpl, Lifetime, Meta, NestedMeta, Pat, PatType, ReturnType, Type,
 };
 
 mod utils;
@@ -56,7 +59,20 @@ pub fn function(args: TokenStream, input: TokenStream) -> TokenStream {
     let orig_fn_name = &orig_fn_ast.sig.ident;
     let gen_function_name = get_gen_method_name(orig_fn_name);
     let register_fn_name = format_ident!("register_{}", orig_fn_name);
-    let js_name = format!("{}", orig_fn_name).to_mixed_case();
+    let js_name = {
+        let args = args.clone();
+        let parsed_args = parse_macro_input!(args as AttributeArgs);
+        match utils::js_name_arg(&parsed_args) {
+            Ok(Some(js_name)) => js_name,
+            Ok(None) => match utils::rename_all_arg(&parsed_args) {
+                Ok(rename_all) => rename_all
+                    .unwrap_or(RenameRule::CamelCase)
+                    .apply(&format!("{}", orig_fn_name)),
+                Err(e) => return e.to_compile_error().into(),
+            },
+            Err(e) => return e.to_compile_error().into(),
+        }
+    };
     let lit = Literal::string(&js_name);
 
     let toks = method(args, orig_fn_ast);
@@ -81,6 +97,13 @@ pub(crate) fn get_gen_method_name(orig_name: &proc_macro2::Ident) -> proc_macro2
 
 #[proc_macro_attribute]
 pub fn neon_class(args: TokenStream, input: TokenStream) -> TokenStream {
+    // lets `extends = Base` be parsed as `extends = "Base"`.
+    let args = utils::stringify_path_args(args.into(), &["extends", "remote"]);
+    // and `instantiate(Decoder<Json> as "JsonDecoder")` as `instantiate(JsonDecoder = "Decoder<Json>")`.
+    let args: TokenStream = match utils::stringify_instantiate_args(args) {
+        Ok(args) => args.into(),
+        Err(e) => return e.to_compile_error().into(),
+    };
     let args_cl = args.clone();
     let parsed_args = parse_macro_input!(args_cl as AttributeArgs);
     if let Some(nested_meta) = parsed_args.first() {
@@ -91,7 +114,8 @@ pub fn neon_class(args: TokenStream, input: TokenStream) -> TokenStream {
                 Meta::Path(path) => {
                     let id = &path.segments.last().unwrap().ident;
                     match id.to_string().as_ref() {
-                        "method" => {
+                        "method" | "getter" | "setter" | "static_method" | "async_constructor"
+                        | "inspect" => {
                             let orig_method_ast = parse_macro_input!(input as ImplItemMethod);
                             return method(args, orig_method_ast);
                         }
@@ -101,6 +125,10 @@ pub fn neon_class(args: TokenStream, input: TokenStream) -> TokenStream {
                         "constructor" => {
                             return constructor(args, input);
                         }
+                        "constant" => {
+                            // exported by the `impl_block` macro, the const itself stays as is.
+                            return input;
+                        }
                         _ => {}
                     }
                 }
@@ -154,11 +182,16 @@ fn constructor(_args: TokenStream, input: TokenStream) -> TokenStream {
             // required by the expansion of `arg_parsing`
             use neon_serde::errors::MapErrIntoThrow;
 
+            // without `new` there is no `new.target`, and `this` isn't a new object to set up.
+            if let neon::context::CallKind::Call = cx.kind() {
+                return cx.throw_type_error("Class constructor cannot be invoked without 'new'");
+            }
+
             #(#arg_parsing)*
 
             let res = #native_method_call
 
-            let this = cx.boxed(res);
+            let this = Self::__neon_box(&mut cx, res);
             cx.this().set(&mut cx, Self::THIS, this)?;
             Ok(cx.undefined())
         }
@@ -196,32 +229,89 @@ fn method<T: AnnotatedFn + quote::ToTokens>(args: TokenStream, orig_method_ast:
     let (output, native_method_result_parser) =
         utils::parse_return_type(output, &output_lifetime, throws_on_err);
 
+    // what gets called depends on the decorated item: a method is called on the JS `this`, an
+    // associated function (a static method) on `Self` and a plain function directly.
+    let callee = if orig_method_ast.has_receiver() {
+        quote! { this.#orig_method_name }
+    } else if orig_method_ast.is_method() {
+        quote! { Self::#orig_method_name }
+    } else {
+        quote! { #orig_method_name }
+    };
+
     let native_method_call = if cx_is_arg {
-        if orig_method_ast.is_method() {
-            quote! {
-                this.#orig_method_name(&mut cx, #(#arg_idents,)*)
-            }
-        } else {
-            quote! {
-                #orig_method_name(&mut cx, #(#arg_idents,)*)
-            }
+        quote! {
+            #callee(&mut cx, #(#arg_idents,)*)
         }
     } else {
-        #[allow(clippy::collapsible_else_if)]
-        if orig_method_ast.is_method() {
-            quote! {
-                this.#orig_method_name(#(#arg_idents,)*)
-            }
-        } else {
-            quote! {
-                #orig_method_name(#(#arg_idents,)*)
-            }
+        quote! {
+            #callee(#(#arg_idents,)*)
         }
     };
 
+    let abort_token = utils::abort_token_arg(orig_method_ast.inputs(), &arg_idents);
+
+    if utils::is_blocking(&parsed_args) {
+        if orig_method_ast.is_async() {
+            panic!(
+                "The function '{}' can't be both async and blocking.",
+                orig_method_name
+            );
+        }
+        if cx_is_arg {
+            panic!(
+                "The blocking function '{}' can't take a Context since it runs on a worker thread.",
+                orig_method_name
+            );
+        }
+        let settle_tokens = match native_method_result_parser {
+            Some(fnct) => fnct(&format_ident!("res"), &quote! { cx }),
+            None => panic!(
+                "The blocking function '{}' can't return a JsResult since it runs on a worker thread.",
+                orig_method_name
+            ),
+        };
+
+        return blocking_method(
+            &orig_method_ast,
+            gen_doc,
+            gen_method_name,
+            arg_parsing,
+            native_method_call,
+            settle_tokens,
+            abort_token,
+        );
+    }
+
+    if orig_method_ast.is_async() {
+        if cx_is_arg {
+            panic!(
+                "The async function '{}' can't take a Context since it runs after the JS call returns.",
+                orig_method_name
+            );
+        }
+
+        return async_method(
+            &orig_method_ast,
+            gen_doc,
+            gen_method_name,
+            arg_parsing,
+            native_method_call,
+            abort_token,
+        );
+    }
+
+    if abort_token.is_some() {
+        panic!(
+            "The function '{}' takes an 'AbortToken' but it doesn't return a Promise.\n\
+        To fix it, make it 'async' or 'blocking'.",
+            orig_method_name
+        );
+    }
+
     let return_call = if let Some(fnct) = native_method_result_parser {
         let result_ident = format_ident!("res");
-        let real_result = fnct(&result_ident);
+        let real_result = fnct(&result_ident, &quote! { &mut cx });
         quote! {
             let #result_ident = #native_method_call;
             #real_result
@@ -230,14 +320,22 @@ fn method<T: AnnotatedFn + quote::ToTokens>(args: TokenStream, orig_method_ast:
         native_method_call
     };
 
-    let this_extract_tokens = if orig_method_ast.is_method() {
-        quote! {
-            let this = cx.this();
-            let this = this.get(&mut cx, Self::THIS)?
-                .downcast_or_throw::<neon::prelude::JsBox<Self>, _>(&mut cx)?;
+    let this_extract_tokens = if orig_method_ast.has_receiver() {
+        // the helpers used here are generated by the `impl_block` macro since that is the one
+        // that knows how `Self` is stored inside the `JsBox`.
+        if utils::takes_mut_self(orig_method_ast.inputs()) {
+            quote! {
+                let this = Self::__neon_this(&mut cx)?;
+                let mut this = Self::__neon_borrow_mut(&this).or_else(|e| cx.throw_error(e))?;
+            }
+        } else {
+            quote! {
+                let this = Self::__neon_this(&mut cx)?;
+                let this = Self::__neon_borrow(&this).or_else(|e| cx.throw_error(e))?;
+            }
         }
     } else {
-        // if this is not a method we don't need the 'this' binding.
+        // if this does not take `self` we don't need the 'this' binding.
         quote! {}
     };
 
@@ -262,14 +360,900 @@ fn method<T: AnnotatedFn + quote::ToTokens>(args: TokenStream, orig_method_ast:
     tokens.into()
 }
 
+/// Tokens to get `Self` into code that outlives the JS call, for `async` and `blocking` methods.
+///
+/// The first ones run on the JS thread and bind an `Arc` of the boxed `Self` to `this`, the second
+/// ones borrow `Self` from it once the method starts running. The borrow uses `?` with a `String`
+/// error, and `.await` when `is_async` so an executor thread doesn't block waiting for a `Mutex`.
+fn shared_this_tokens<T: AnnotatedFn>(
+    orig_method_ast: &T,
+    is_async: bool,
+) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+    if !orig_method_ast.has_receiver() {
+        return (quote! {}, quote! {});
+    }
+
+    let borrow_this_tokens = match (utils::takes_mut_self(orig_method_ast.inputs()), is_async) {
+        (true, false) => quote! {
+            let mut this = Self::__neon_borrow_shared_mut(&this)?;
+        },
+        (false, false) => quote! {
+            let this = Self::__neon_borrow_shared(&this)?;
+        },
+        (true, true) => quote! {
+            let mut this = Self::__neon_borrow_shared_mut_async(&this).await?;
+        },
+        (false, true) => quote! {
+            let this = Self::__neon_borrow_shared_async(&this).await?;
+        },
+    };
+
+    (
+        quote! {
+            let this = Self::__neon_this(&mut cx)?;
+            let this = Self::__neon_share(&this);
+        },
+        borrow_this_tokens,
+    )
+}
+
+/// Generated method for a `blocking` method or function.
+///
+/// The args are parsed on the JS thread, then the decorated body runs on a
+/// `neon_class_runtime` worker thread and its result is converted back on the JS thread, the same
+/// way as for a regular method, to settle the returned promise.
+fn blocking_method<T: AnnotatedFn + quote::ToTokens>(
+    orig_method_ast: &T,
+    gen_doc: proc_macro2::TokenStream,
+    gen_method_name: proc_macro2::Ident,
+    arg_parsing: Vec<proc_macro2::TokenStream>,
+    native_method_call: proc_macro2::TokenStream,
+    settle_tokens: proc_macro2::TokenStream,
+    abort_token: Option<proc_macro2::Ident>,
+) -> TokenStream {
+    let (share_this_tokens, borrow_this_tokens) = shared_this_tokens(orig_method_ast, false);
+
+    // the body can't be interrupted, an aborted call rejects the promise right away and what the
+    // body returns is discarded.
+    let (clone_abort_token, abortable_deferred, take_deferred, settle_abort_tokens) =
+        match abort_token {
+            Some(abort_token) => (
+                quote! {
+                    let abort_token = #abort_token.clone();
+                },
+                quote! {
+                    let deferred = neon_class_runtime::abort::AbortableDeferred::new(&abort_token, cx.channel(), deferred);
+                },
+                quote! {
+                    let deferred = match deferred.take() {
+                        Some(deferred) => deferred,
+                        None => return,
+                    };
+                },
+                quote! {
+                    abort_token.release(cx)?;
+                },
+            ),
+            None => (quote! {}, quote! {}, quote! {}, quote! {}),
+        };
+
+    let tokens = quote! {
+            #orig_method_ast
+
+            ///
+            #gen_doc
+            pub fn #gen_method_name<'ctx>(mut cx: neon::prelude::FunctionContext<'ctx>) -> neon::prelude::JsResult<'ctx, neon::prelude::JsPromise> {
+                use neon::prelude::{Context, Object};
+                // required by the expansion of `arg_parsing`
+                use neon_serde::errors::MapErrIntoThrow;
+
+                #(#arg_parsing)*
+
+                #share_this_tokens
+
+                #clone_abort_token
+
+                let channel = cx.channel();
+                let (deferred, promise) = cx.promise();
+                #abortable_deferred
+
+                neon_class_runtime::spawn_blocking(move || {
+                    // a panic rejects the promise like an `Err` would.
+                    let res = neon_class_runtime::blocking::catch_panic(|| {
+                        #borrow_this_tokens
+                        Ok::<_, String>(#native_method_call)
+                    });
+
+                    #take_deferred
+                    channel.settle_with(deferred, move |cx| {
+                        #settle_abort_tokens
+                        let res = res.or_else(|e| cx.throw_error(e))?;
+                        #settle_tokens
+                    });
+                });
+
+                Ok(promise)
+            }
+    };
+
+    tokens.into()
+}
+
+/// Generated method for an `async fn`.
+///
+/// The args are parsed on the JS thread, then the future is driven by the `neon_class_runtime`
+/// executor and its output settles the returned promise back on the JS thread.
+fn async_method<T: AnnotatedFn + quote::ToTokens>(
+    orig_method_ast: &T,
+    gen_doc: proc_macro2::TokenStream,
+    gen_method_name: proc_macro2::Ident,
+    arg_parsing: Vec<proc_macro2::TokenStream>,
+    native_method_call: proc_macro2::TokenStream,
+    abort_token: Option<proc_macro2::Ident>,
+) -> TokenStream {
+    let settle_tokens = utils::parse_async_return_type(orig_method_ast.get_ret_type());
+
+    let (share_this_tokens, borrow_this_tokens) = shared_this_tokens(orig_method_ast, true);
+
+    // a panic rejects the promise like an `Err` would.
+    let future_tokens = quote! {
+        neon_class_runtime::executor::catch_panic(async {
+            #borrow_this_tokens
+            Ok::<_, String>(#native_method_call.await)
+        })
+    };
+
+    // an aborted call drops the future and rejects the promise right away.
+    let (clone_abort_token, future_tokens, settle_abort_tokens) =
+        if let Some(abort_token) = abort_token {
+            (
+                quote! {
+                    let abort_token = #abort_token.clone();
+                },
+                quote! {
+                    neon_class_runtime::abort::abortable(&abort_token, #future_tokens)
+                },
+                quote! {
+                    abort_token.release(cx)?;
+                    let res = res.or_else(|_| neon_class_runtime::abort::throw_abort_error(cx))?;
+                },
+            )
+        } else {
+            (quote! {}, future_tokens, quote! {})
+        };
+
+    let tokens = quote! {
+            #orig_method_ast
+
+            ///
+            #gen_doc
+            pub fn #gen_method_name<'ctx>(mut cx: neon::prelude::FunctionContext<'ctx>) -> neon::prelude::JsResult<'ctx, neon::prelude::JsPromise> {
+                use neon::prelude::{Context, Object};
+                // required by the expansion of `arg_parsing`
+                use neon_serde::errors::MapErrIntoThrow;
+
+                #(#arg_parsing)*
+
+                #share_this_tokens
+
+                #clone_abort_token
+
+                let channel = cx.channel();
+                let (deferred, promise) = cx.promise();
+
+                neon_class_runtime::spawn(move || async move {
+                    let res = #future_tokens.await;
+
+                    channel.settle_with(deferred, move |cx| {
+                        #settle_abort_tokens
+                        let res = res.or_else(|e| cx.throw_error(e))?;
+                        #settle_tokens
+                    });
+                });
+
+                Ok(promise)
+            }
+    };
+
+    tokens.into()
+}
+
+/// Tokens that get `Object.defineProperty` from the JS global scope as `define_property`.
+///
+/// The `Object` constructor is bound to `object_ctor` so it can be used as `this` when calling it.
+fn object_define_property_tok() -> proc_macro2::TokenStream {
+    quote! {
+        let object_ctor = neon::prelude::Context::global(cx)
+            .get(cx, "Object")?
+            .downcast_or_throw::<neon::prelude::JsObject, _>(cx)?;
+        let define_property = object_ctor
+            .get(cx, "defineProperty")?
+            .downcast_or_throw::<neon::prelude::JsFunction, _>(cx)?;
+    }
+}
+
+/// Tokens that get `Object.setPrototypeOf` from the JS global scope as `set_prototype_of`.
+///
+/// Like [`object_define_property_tok`], the `Object` constructor is bound to `object_ctor`.
+fn object_set_prototype_of_tok() -> proc_macro2::TokenStream {
+    quote! {
+        let object_ctor = neon::prelude::Context::global(cx)
+            .get(cx, "Object")?
+            .downcast_or_throw::<neon::prelude::JsObject, _>(cx)?;
+        let set_prototype_of = object_ctor
+            .get(cx, "setPrototypeOf")?
+            .downcast_or_throw::<neon::prelude::JsFunction, _>(cx)?;
+    }
+}
+
+/// Tokens that get `Object.create` from the JS global scope as `object_create`.
+///
+/// Like [`object_define_property_tok`], the `Object` constructor is bound to `object_ctor`.
+fn object_create_tok() -> proc_macro2::TokenStream {
+    quote! {
+        let object_ctor = neon::prelude::Context::global(cx)
+            .get(cx, "Object")?
+            .downcast_or_throw::<neon::prelude::JsObject, _>(cx)?;
+        let object_create = object_ctor
+            .get(cx, "create")?
+            .downcast_or_throw::<neon::prelude::JsFunction, _>(cx)?;
+    }
+}
+
+/// Tokens that get the symbol of `symbol_key` from the JS global scope as `key`.
+fn symbol_tok(symbol_key: &SymbolKey) -> proc_macro2::TokenStream {
+    let symbol_ctor_tok = quote! {
+        let symbol_ctor = neon::prelude::Context::global(cx)
+            .get(cx, "Symbol")?
+            .downcast_or_throw::<neon::prelude::JsObject, _>(cx)?;
+    };
+    match symbol_key {
+        SymbolKey::WellKnown(name) => quote! {
+            #symbol_ctor_tok
+            let key = symbol_ctor.get(cx, #name)?;
+        },
+        SymbolKey::Registered(description) => quote! {
+            #symbol_ctor_tok
+            let symbol_for = symbol_ctor
+                .get(cx, "for")?
+                .downcast_or_throw::<neon::prelude::JsFunction, _>(cx)?;
+            let description = neon::prelude::JsString::new(cx, #description);
+            let key = symbol_for.call(cx, symbol_ctor, [description.upcast::<neon::prelude::JsValue>()])?;
+        },
+    }
+}
+
+/// Generates `call_<method>` for a `#[neon_class(method, virtual)]` method.
+///
+/// It calls the method through the JS object so the override of a JS subclass is used, and calls
+/// the rust method directly when the method wasn't overridden. The generated methods of the
+/// prototype are marked with `Self::THIS` to tell them apart from the overrides.
+fn virtual_method_tok(method: &NeonMacrosAttrs, naming: JsNaming) -> proc_macro2::TokenStream {
+    let sig = &method.method.sig;
+    let name = &sig.ident;
+    let call_name = format_ident!("call_{}", name);
+    let receiver = sig.receiver().unwrap();
+    let js_name = naming.name_tok(|rename_all| method.js_name(rename_all));
+    let (arg_idents, arg_types): (Vec<_>, Vec<_>) = method.virtual_args().into_iter().unzip();
+    let output = match &sig.output {
+        ReturnType::Default => quote! { () },
+        ReturnType::Type(_, ty) => quote! { #ty },
+    };
+    let doc = proc_macro2::TokenStream::from_str(&format!(
+        "/// Calls [`{0}`](#method.{0}) on the JS object `this`, using the override of a JS subclass if there is one.",
+        name
+    ))
+    .unwrap();
+
+    quote! {
+        #doc
+        pub fn #call_name<'a, C: neon::prelude::Context<'a>>(#receiver, cx: &mut C, this: neon::prelude::Handle<'a, neon::prelude::JsObject>, #(#arg_idents: #arg_types,)*) -> neon::prelude::NeonResult<#output> {
+            use neon::prelude::Object;
+            use neon_serde::errors::MapErrIntoThrow;
+
+            let method = this.get(cx, #js_name)?;
+            if let Ok(method) = method.downcast::<neon::prelude::JsFunction, _>(cx) {
+                let is_overridden = !method.get(cx, Self::THIS)?.is_a::<neon::prelude::JsBoolean, _>(cx);
+                if is_overridden {
+                    let args: Vec<neon::prelude::Handle<neon::prelude::JsValue>> = vec![
+                        #(neon_serde::to_value(cx, &#arg_idents).map_err_into_throw(cx)?,)*
+                    ];
+                    let res = method.call(cx, this, args)?;
+                    return neon_serde::from_value(cx, res).map_err_into_throw(cx);
+                }
+            }
+            Ok(self.#name(#(#arg_idents,)*))
+        }
+    }
+}
+
+/// Tokens that set the methods and accessors of `impl_tree` on `prototype`.
+fn prototype_items_tok(impl_tree: &ImplTree, naming: JsNaming) -> proc_macro2::TokenStream {
+    // these are the names of the generated methods that get created by the `method` macro.
+    let gen_method_names = impl_tree
+        .methods
+        .iter()
+        .map(|e| get_gen_method_name(&e.method.sig.ident));
+
+    // for the same set of generated methods, set them on their name for the JS side or on their
+    // symbol.
+    let set_methods = impl_tree.methods.iter().map(|e| match e.symbol_key() {
+        Some(symbol_key) => {
+            let symbol_tok = symbol_tok(&symbol_key);
+            quote! {
+                #symbol_tok
+                prototype.set(cx, key, f)?;
+            }
+        }
+        None => {
+            let js_name = naming.name_tok(|rename_all| e.js_name(rename_all));
+            quote! {
+                prototype.set(cx, #js_name, f)?;
+            }
+        }
+    });
+
+    // virtual methods are marked so `call_<method>` can tell them apart from the JS overrides.
+    let virtual_marks = impl_tree.methods.iter().map(|e| {
+        if e.is_virtual() {
+            quote! {
+                let marker = neon::prelude::JsBoolean::new(cx, true);
+                f.set(cx, Self::THIS, marker)?;
+            }
+        } else {
+            quote! {}
+        }
+    });
+
+    // getters and setters are installed as accessor properties via `Object.defineProperty`.
+    let accessors = impl_tree.accessors(naming.pairing_rule());
+    let accessors_setup_tok = if accessors.is_empty() {
+        quote! {}
+    } else {
+        let define_property_tok = object_define_property_tok();
+        let define_each = accessors.iter().map(|accessor| {
+            let js_name = naming.name_tok(|rename_all| accessor.named_by.js_name(rename_all));
+            let get = accessor.getter.map(|getter| {
+                let gen_getter_name = get_gen_method_name(&getter.sig.ident);
+                quote! {
+                    let f = neon::prelude::JsFunction::new(cx, Self::#gen_getter_name)?;
+                    descriptor.set(cx, "get", f)?;
+                }
+            });
+            let set = accessor.setter.map(|setter| {
+                let gen_setter_name = get_gen_method_name(&setter.sig.ident);
+                quote! {
+                    let f = neon::prelude::JsFunction::new(cx, Self::#gen_setter_name)?;
+                    descriptor.set(cx, "set", f)?;
+                }
+            });
+            quote! {
+                let descriptor = neon::prelude::JsObject::new(cx);
+                #get
+                #set
+                let configurable = neon::prelude::JsBoolean::new(cx, true);
+                descriptor.set(cx, "configurable", configurable)?;
+                let name = neon::prelude::JsString::new(cx, #js_name);
+                define_property.call(cx, object_ctor, [
+                    prototype.upcast::<neon::prelude::JsValue>(),
+                    name.upcast(),
+                    descriptor.upcast(),
+                ])?;
+            }
+        });
+        quote! {
+            #define_property_tok
+            #(#define_each)*
+        }
+    };
+
+    quote! {
+        #(
+            let f = neon::prelude::JsFunction::new(cx, Self::#gen_method_names)?;
+            #virtual_marks
+            #set_methods
+        )*
+
+        #accessors_setup_tok
+    }
+}
+
+/// Tokens that set the static methods, the async constructor and the constants on `constructor`.
+fn constructor_items_tok(
+    impl_tree: &ImplTree,
+    exported_consts: &[ExportedConst],
+    naming: JsNaming,
+) -> proc_macro2::TokenStream {
+    // static methods, and the async constructor, are set on the constructor itself, not on the
+    // prototype.
+    let static_gen_method_names = impl_tree
+        .static_methods
+        .iter()
+        .chain(&impl_tree.async_constructor)
+        .map(|e| get_gen_method_name(&e.method.sig.ident));
+    let static_js_names = impl_tree
+        .static_methods
+        .iter()
+        .chain(&impl_tree.async_constructor)
+        .map(|e| naming.name_tok(|rename_all| e.js_name(rename_all)));
+
+    // constants are serialized once and set as read-only properties on the constructor.
+    let constants_setup_tok = if exported_consts.is_empty() {
+        quote! {}
+    } else {
+        let define_property_tok = object_define_property_tok();
+        let const_idents = exported_consts.iter().map(|c| &c.ident);
+        let const_js_names = exported_consts
+            .iter()
+            .map(|c| naming.name_tok(|rename_all| c.js_name(rename_all)));
+        quote! {
+            // required to serialize the constants
+            use neon_serde::errors::MapErrIntoThrow;
+
+            #define_property_tok
+            #(
+                let value = neon_serde::to_value(cx, &Self::#const_idents).map_err_into_throw(cx)?;
+                let descriptor = neon::prelude::JsObject::new(cx);
+                descriptor.set(cx, "value", value)?;
+                let enumerable = neon::prelude::JsBoolean::new(cx, true);
+                descriptor.set(cx, "enumerable", enumerable)?;
+                let name = neon::prelude::JsString::new(cx, #const_js_names);
+                define_property.call(cx, object_ctor, [
+                    constructor.upcast::<neon::prelude::JsValue>(),
+                    name.upcast(),
+                    descriptor.upcast(),
+                ])?;
+            )*
+        }
+    };
+
+    quote! {
+        #(
+            let f = neon::prelude::JsFunction::new(cx, Self::#static_gen_method_names)?;
+            constructor.set(cx, #static_js_names, f)?;
+        )*
+
+        #constants_setup_tok
+    }
+}
+
+/// The methods generated for the traits of `derive_js(...)`, with the tokens that set them on
+/// `prototype` and on `constructor`.
+///
+/// `object_ty` is the type the traits are implemented for, `Self` unless the `impl_block` is
+/// `remote`.
+fn derive_js_tok(
+    derive_js: &[proc_macro2::Ident],
+    object_ty: &proc_macro2::TokenStream,
+) -> (
+    Vec<proc_macro2::TokenStream>,
+    proc_macro2::TokenStream,
+    proc_macro2::TokenStream,
+) {
+    let borrow_this_tok = quote! {
+        let this = Self::__neon_this(&mut cx)?;
+        let this = Self::__neon_borrow(&this).or_else(|e| cx.throw_error(e))?;
+        let this: &#object_ty = &this;
+    };
+    // the same instance can't be borrowed twice when it's in a `Mutex`, so it's only compared
+    // with itself.
+    let borrow_other_tok = |compare: proc_macro2::TokenStream| {
+        quote! {
+            if is_this {
+                #compare(this, this)
+            } else {
+                let other = Self::__neon_this_of(&mut cx, other)?;
+                let other = Self::__neon_borrow(&other).or_else(|e| cx.throw_error(e))?;
+                let other: &#object_ty = &other;
+                #compare(this, other)
+            }
+        }
+    };
+
+    let mut fns = Vec::new();
+    let mut prototype_tok = quote! {};
+    let mut constructor_tok = quote! {};
+    for derived in derive_js {
+        // `compare(a, b)` is static so it can be given to `Array.prototype.sort`, the rest are
+        // methods.
+        let (js_name, is_static, output, body) = match derived.to_string().as_str() {
+            "Display" => (
+                "toString",
+                false,
+                quote! { neon::prelude::JsString },
+                quote! {
+                    #borrow_this_tok
+                    let s = std::string::ToString::to_string(this);
+                    Ok(cx.string(s))
+                },
+            ),
+            "Clone" => (
+                "clone",
+                false,
+                quote! { neon::prelude::JsObject },
+                quote! {
+                    let obj = {
+                        #borrow_this_tok
+                        std::clone::Clone::clone(this)
+                    };
+                    Self::to_js_obj(&mut cx, obj)
+                },
+            ),
+            "PartialEq" => {
+                let equals_tok = borrow_other_tok(quote! { std::cmp::PartialEq::eq });
+                (
+                    "equals",
+                    false,
+                    quote! { neon::prelude::JsBoolean },
+                    quote! {
+                        let other = cx.argument::<neon::prelude::JsObject>(0)?;
+                        let is_this = cx.this().strict_equals(&mut cx, other);
+                        #borrow_this_tok
+                        let equal = #equals_tok;
+                        Ok(cx.boolean(equal))
+                    },
+                )
+            }
+            "Ord" => {
+                let compare_tok = borrow_other_tok(quote! { std::cmp::Ord::cmp });
+                (
+                    "compare",
+                    true,
+                    quote! { neon::prelude::JsNumber },
+                    quote! {
+                        let this = cx.argument::<neon::prelude::JsObject>(0)?;
+                        let other = cx.argument::<neon::prelude::JsObject>(1)?;
+                        let is_this = this.strict_equals(&mut cx, other);
+                        let this = Self::__neon_this_of(&mut cx, this)?;
+                        let this = Self::__neon_borrow(&this).or_else(|e| cx.throw_error(e))?;
+                        let this: &#object_ty = &this;
+                        let ordering = #compare_tok;
+                        Ok(cx.number(ordering as i32))
+                    },
+                )
+            }
+            _ => unreachable!(),
+        };
+
+        let gen_name = format_ident!("__neon_gen_derive_{}", js_name.to_snake_case());
+        fns.push(quote! {
+            #[doc(hidden)]
+            pub fn #gen_name(mut cx: neon::prelude::FunctionContext) -> neon::prelude::JsResult<#output> {
+                #body
+            }
+        });
+        let (target_tok, target) = if is_static {
+            (&mut constructor_tok, format_ident!("constructor"))
+        } else {
+            (&mut prototype_tok, format_ident!("prototype"))
+        };
+        target_tok.extend(quote! {
+            let f = neon::prelude::JsFunction::new(cx, Self::#gen_name)?;
+            #target.set(cx, #js_name, f)?;
+        });
+    }
+    (fns, prototype_tok, constructor_tok)
+}
+
+/// The `on`, `once`, `off` and `removeAllListeners` methods of `#[neon_class(impl_block, events)]`,
+/// with the tokens that set them on the prototype. The listeners are kept by the `field` of the
+/// struct, `events` unless named with `events = "..."`.
+fn events_tok(
+    object_ty: &proc_macro2::TokenStream,
+    field: &proc_macro2::Ident,
+) -> (Vec<proc_macro2::TokenStream>, proc_macro2::TokenStream) {
+    let mut fns = Vec::new();
+    let mut prototype_tok = quote! {};
+    for js_name in ["on", "once", "off", "removeAllListeners"] {
+        let gen_name = format_ident!("__neon_gen_events_{}", js_name.to_snake_case());
+        let (args_tok, listen_tok) = if js_name == "removeAllListeners" {
+            (
+                quote! {
+                    // without an event, like `EventEmitter.removeAllListeners()`, all of them go.
+                    let event = match cx.argument_opt(0) {
+                        Some(event) => event
+                            .downcast::<neon::prelude::JsString, _>(&mut cx)
+                            .ok()
+                            .map(|event| event.value(&mut cx)),
+                        None => None,
+                    };
+                },
+                quote! { events.remove_all_listeners(&mut cx, event.as_deref()) },
+            )
+        } else {
+            let args_tok = quote! {
+                let event = cx.argument::<neon::prelude::JsString>(0)?.value(&mut cx);
+                let listener = cx.argument::<neon::prelude::JsFunction>(1)?;
+            };
+            let listen_tok = if js_name == "off" {
+                quote! { events.remove_listener(&mut cx, &event, listener) }
+            } else {
+                let once = js_name == "once";
+                quote! { events.add_listener(&mut cx, event, listener, #once) }
+            };
+            (args_tok, listen_tok)
+        };
+        fns.push(quote! {
+            #[doc(hidden)]
+            pub fn #gen_name(mut cx: neon::prelude::FunctionContext) -> neon::prelude::JsResult<neon::prelude::JsObject> {
+                #args_tok
+                {
+                    let this = Self::__neon_this(&mut cx)?;
+                    let this = Self::__neon_borrow(&this).or_else(|e| cx.throw_error(e))?;
+                    let this: &#object_ty = &this;
+                    let events: &neon_class_runtime::events::EventSender = &this.#field;
+                    #listen_tok;
+                }
+                // returns the instance so the calls can be chained, like on an `EventEmitter`.
+                Ok(cx.this())
+            }
+        });
+        prototype_tok.extend(quote! {
+            let f = neon::prelude::JsFunction::new(cx, Self::#gen_name)?;
+            prototype.set(cx, #js_name, f)?;
+        });
+    }
+    (fns, prototype_tok)
+}
+
+/// The hidden methods of a `#[neon_class(impl_block, part = "...")]` block, that set its items on
+/// the class of the main `impl_block`.
+fn impl_part_fns(
+    part: &str,
+    impl_tree: &ImplTree,
+    exported_consts: &[ExportedConst],
+    naming: JsNaming,
+) -> [proc_macro2::TokenStream; 2] {
+    let setup_prototype_name = format_ident!("__neon_setup_prototype_{}", part);
+    let setup_constructor_name = format_ident!("__neon_setup_constructor_{}", part);
+    let prototype_items_tok = prototype_items_tok(impl_tree, naming);
+    let constructor_items_tok = constructor_items_tok(impl_tree, exported_consts, naming);
+    [
+        quote! {
+            #[doc(hidden)]
+            pub fn #setup_prototype_name<'a, C: neon::prelude::Context<'a>>(cx: &mut C, prototype: neon::prelude::Handle<'a, neon::prelude::JsObject>) -> neon::prelude::NeonResult<()> {
+                use neon::prelude::Object;
+
+                #prototype_items_tok
+
+                Ok(())
+            }
+        },
+        quote! {
+            #[doc(hidden)]
+            pub fn #setup_constructor_name<'a, C: neon::prelude::Context<'a>>(cx: &mut C, constructor: neon::prelude::Handle<'a, neon::prelude::JsFunction>) -> neon::prelude::NeonResult<()> {
+                use neon::prelude::Object;
+
+                #constructor_items_tok
+
+                Ok(())
+            }
+        },
+    ]
+}
+
+/// Splits a trait impl decorated with `impl_block` into the trait impl itself and an inherent impl
+/// of the same type with the generated methods of its decorated items.
+///
+/// The decorated methods can't expand in the trait impl, so their `neon_class` attribute is removed
+/// and expanded here instead, keeping only the generated methods.
+fn split_trait_impl(mut trait_impl: ItemImpl) -> (ItemImpl, ItemImpl) {
+    let mut generated = Vec::new();
+    for item in trait_impl.items.iter_mut() {
+        let method = match item {
+            ImplItem::Method(method) => method,
+            _ => continue,
+        };
+        let attr_idx = match method.attrs.iter().position(utils::is_neon_class_attr) {
+            Some(idx) => idx,
+            None => continue,
+        };
+        let attr = method.attrs.remove(attr_idx);
+        let args = match attr.tokens.into_iter().next() {
+            Some(proc_macro2::TokenTree::Group(group)) => group.stream(),
+            _ => panic!(
+                "Invalid neon_class attribute on '{}', expected args like #[neon_class(method)]",
+                method.sig.ident
+            ),
+        };
+
+        let expanded: proc_macro2::TokenStream =
+            neon_class(args.into(), method.to_token_stream().into()).into();
+        let expanded: ItemImpl = syn::parse2(quote! { impl Expanded { #expanded } })
+            .expect("The expanded neon_class attribute should be valid impl items");
+        generated.extend(expanded.items.into_iter().filter(
+            |item| !matches!(item, ImplItem::Method(m) if m.sig.ident == method.sig.ident),
+        ));
+    }
+
+    // `cfg`s still apply, other attributes (like `async_trait`) are meant for the trait impl.
+    let inherent_impl = ItemImpl {
+        attrs: trait_impl
+            .attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("cfg"))
+            .cloned()
+            .collect(),
+        defaultness: None,
+        unsafety: None,
+        trait_: None,
+        items: generated,
+        ..trait_impl.clone()
+    };
+    (trait_impl, inherent_impl)
+}
+
+/// Gives the bodyless declarations of a `remote` impl_block a body so they can be expanded like
+/// the other methods. A static method forwards to the one of the remote type, a method taking
+/// `self` is called on the boxed remote type by the generated glue so its name is returned to
+/// leave it out of the output, see [`strip_remote_items`].
+fn declare_remote_items(impl_ast: &mut ItemImpl, remote: &syn::Path) -> Vec<proc_macro2::Ident> {
+    if impl_ast.trait_.is_some() {
+        panic!(
+            "A 'remote' impl_block must be an inherent impl of a local type, like 'impl Marker {{ ... }}'."
+        );
+    }
+    let mut receivers = Vec::new();
+    for item in impl_ast.items.iter_mut() {
+        let method = match item {
+            ImplItem::Method(method) => method,
+            _ => continue,
+        };
+        // syn keeps the `;` of a method without a body as its only statement.
+        let is_declared = matches!(
+            method.block.stmts.as_slice(),
+            [syn::Stmt::Item(syn::Item::Verbatim(semi))] if semi.to_string() == ";"
+        );
+        let name = &method.sig.ident;
+        let takes_self = method
+            .sig
+            .inputs
+            .iter()
+            .any(|arg| matches!(arg, FnArg::Receiver(_)));
+
+        if takes_self {
+            if !is_declared && method.attrs.iter().any(utils::is_neon_class_attr) {
+                panic!(
+                    "The method '{}' of a 'remote' impl_block takes 'self' so it's called on {}, declare it without a body like 'fn {}(...);'",
+                    name,
+                    quote! { #remote },
+                    name
+                );
+            }
+            if is_declared {
+                receivers.push(name.clone());
+                method.block = syn::parse_quote! {{ unreachable!() }};
+            }
+        } else if is_declared {
+            let args = method.sig.inputs.iter().map(|arg| match arg {
+                FnArg::Typed(PatType { pat, .. }) => match pat.as_ref() {
+                    Pat::Ident(id) => &id.ident,
+                    _ => panic!(
+                        "The args of '{}' must be plain names to forward them to {}",
+                        name,
+                        quote! { #remote }
+                    ),
+                },
+                FnArg::Receiver(_) => unreachable!(),
+            });
+            let await_token = method.sig.asyncness.map(|_| quote! { .await });
+            method.block = syn::parse_quote! {{ <#remote>::#name(#(#args),*)#await_token }};
+        }
+    }
+    receivers
+}
+
+/// Takes the expanded `receivers` out of a `remote` impl_block and has `Self` in the signatures of
+/// the rest mean the remote type, so static methods and constructors can return it.
+fn strip_remote_items(
+    mut source_impl: ItemImpl,
+    remote: &syn::Path,
+    receivers: &[proc_macro2::Ident],
+) -> ItemImpl {
+    source_impl
+        .items
+        .retain(|item| !matches!(item, ImplItem::Method(m) if receivers.contains(&m.sig.ident)));
+    let substitutions = [(format_ident!("Self"), quote! { #remote })];
+    for item in source_impl.items.iter_mut() {
+        if let ImplItem::Method(method) = item {
+            method.sig = syn::parse2(utils::substitute_idents(
+                method.sig.to_token_stream(),
+                &substitutions,
+            ))
+            .expect("Substituting `Self` should give a valid signature");
+        }
+    }
+    // a `new` returns the remote type now, not the marker.
+    source_impl
+        .attrs
+        .push(syn::parse_quote! { #[allow(clippy::new_ret_no_self)] });
+    source_impl
+}
+
 /// This macro is used to decorate impl blocks.
 ///
 /// ## Examples
 /// The following are examples of how to use some of the methods generated by this macro.
 #[doc = include_str!("../docs/to_js_obj.md")]
 ///
-fn impl_block(_args: TokenStream, input: TokenStream) -> TokenStream {
-    let mut impl_ast = parse_macro_input!(input as ItemImpl);
+fn impl_block(args: TokenStream, input: TokenStream) -> TokenStream {
+    let parsed_args = parse_macro_input!(args as AttributeArgs);
+    let impl_args = match ImplBlockArgs::new(&parsed_args) {
+        Ok(impl_args) => impl_args,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let impl_ast = parse_macro_input!(input as ItemImpl);
+
+    if impl_args.instantiate.is_empty() {
+        return expand_impl_block(impl_args, impl_ast, false);
+    }
+
+    if impl_ast.generics.params.is_empty() {
+        return syn::Error::new_spanned(
+            &impl_ast.self_ty,
+            format!(
+                "'instantiate' is for generic impl blocks but '{}' isn't generic.",
+                impl_ast.self_ty.to_token_stream()
+            ),
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    // each concrete type gets its own class from an `impl` with only the generated items, the
+    // generic `impl` keeps the decorated ones.
+    let (generic_impl, _) = split_trait_impl(impl_ast.clone());
+    let instances = impl_args.instantiate.iter().map(|(ty, js_name)| {
+        let instance_args = ImplBlockArgs {
+            js_name: Some(js_name.clone()),
+            instantiate: Vec::new(),
+            ..impl_args.clone()
+        };
+        let instance_impl = utils::instantiate_impl(&impl_ast, ty);
+        proc_macro2::TokenStream::from(expand_impl_block(instance_args, instance_impl, true))
+    });
+
+    let tokens = quote! {
+        #generic_impl
+        #(#instances)*
+    };
+
+    tokens.into()
+}
+
+/// Generates the glue of an `impl_block`.
+///
+/// With `generated_only`, only the generated items are kept, the decorated ones are in another
+/// `impl` (like the generic `impl` of an `instantiate`).
+fn expand_impl_block(
+    impl_args: ImplBlockArgs,
+    mut impl_ast: ItemImpl,
+    generated_only: bool,
+) -> TokenStream {
+    let remote_receivers = match &impl_args.remote {
+        Some(remote) => declare_remote_items(&mut impl_ast, remote),
+        None => Vec::new(),
+    };
+
+    // the decorated items, the generated ones are added to `impl_ast`.
+    let decorated_items = impl_ast.items.clone();
+
+    // a trait impl can only have the items of the trait, so the generated ones go in an inherent
+    // impl of the type instead. The same goes for a remote impl_block, its declarations are
+    // expanded and then taken out.
+    let mut trait_impl =
+        if impl_ast.trait_.is_some() || generated_only || impl_args.remote.is_some() {
+            let (trait_impl, inherent_impl) = split_trait_impl(impl_ast);
+            impl_ast = inherent_impl;
+            Some(trait_impl)
+                .filter(|_| !generated_only)
+                .map(|trait_impl| match &impl_args.remote {
+                    Some(remote) => strip_remote_items(trait_impl, remote, &remote_receivers),
+                    None => trait_impl,
+                })
+        } else {
+            None
+        };
 
     // Find the struct name for this impl block i.e. for `impl MyStruct { ...`
     // the struct_name is MyStruct.
@@ -280,32 +1264,71 @@ fn impl_block(_args: TokenStream, input: TokenStream) -> TokenStream {
         panic!("No struct_name for impl block")
     };
 
-    // adds a THIS const to the `impl` block.
-    let this_token = {
-        let this = Literal::string(&format!("__this_{}", struct_name_as_str));
-        let this = quote! {
-            const THIS: &'static str = #this;
+    // adds a THIS const to the `impl` block, a part uses the one of the main `impl_block`. It's
+    // named after the whole type so each instantiation of a generic struct gets its own.
+    if impl_args.part.is_none() {
+        let this_token = {
+            let self_ty = impl_ast
+                .self_ty
+                .to_token_stream()
+                .to_string()
+                .replace(' ', "");
+            let this = Literal::string(&format!("__this_{}", self_ty));
+            let this = quote! {
+                const THIS: &'static str = #this;
+            };
+            let this: proc_macro::TokenStream = this.into();
+            parse_macro_input!(this as ImplItemConst)
         };
-        let this: proc_macro::TokenStream = this.into();
-        parse_macro_input!(this as ImplItemConst)
-    };
-    impl_ast.items.push(ImplItem::Const(this_token));
+        impl_ast.items.push(ImplItem::Const(this_token));
+    }
 
     // find the decorated methods we care about, those with neon_class(...)
-    let attrs_for_each_decorated_method = impl_ast
-        .items
+    let mut attrs_for_each_decorated_method = match decorated_items
         .iter()
-        .map(|item| {
-            if let ImplItem::Method(method) = item {
-                NeonMacrosAttrs::new(method.clone())
-            } else {
-                None
-            }
+        .filter_map(|item| match item {
+            ImplItem::Method(method) => NeonMacrosAttrs::new(method.clone()).transpose(),
+            _ => None,
         })
-        .flatten()
-        .collect::<Vec<NeonMacrosAttrs>>();
+        .collect::<syn::Result<Vec<NeonMacrosAttrs>>>()
+    {
+        Ok(attrs) => attrs,
+        Err(e) => return e.to_compile_error().into(),
+    };
 
-    if attrs_for_each_decorated_method.is_empty() {
+    // a part can't know if the main block is mutable when it expands, so it has to be marked
+    // `mutable` itself to have `&mut self` methods. The others are left out of the class so only
+    // the error is reported.
+    let mut mutable_errors = Vec::new();
+    if let (Some(part), false) = (&impl_args.part, impl_args.boxed.is_mutable()) {
+        attrs_for_each_decorated_method.retain(|m| {
+            let method = &m.method.sig.ident;
+            if !utils::takes_mut_self(&m.method.sig.inputs) {
+                return true;
+            }
+            let message = format!(
+                "The method '{}' takes '&mut self' but the part '{}' of struct {} is not mutable.\n\
+                To fix it, use '#[neon_class(impl_block, part = \"{}\", mutable)]' on a struct whose main 'impl_block' is mutable, or take '&self' instead.",
+                method, part, struct_name, part
+            );
+            mutable_errors.push(syn::Error::new(method.span(), message).to_compile_error());
+            for item in impl_ast
+                .items
+                .iter_mut()
+                .chain(trait_impl.iter_mut().flat_map(|t| t.items.iter_mut()))
+            {
+                match item {
+                    ImplItem::Method(item) if item.sig.ident == *method => {
+                        item.attrs.retain(|attr| !utils::is_neon_class_attr(attr))
+                    }
+                    _ => {}
+                }
+            }
+            false
+        });
+    }
+
+    if attrs_for_each_decorated_method.is_empty() && mutable_errors.is_empty() {
         panic!(
             "Found an 'impl_block' argument for struct {} but no constructor or methods were found.\n\
         This could be because:\n  \
@@ -316,51 +1339,588 @@ fn impl_block(_args: TokenStream, input: TokenStream) -> TokenStream {
     }
 
     let impl_tree = ImplTree::new(attrs_for_each_decorated_method);
-    // these are the names of the generated methods that get created by the `method` macro.
-    let gen_method_names: Vec<proc_macro2::Ident> = impl_tree
-        .methods
-        .iter()
-        .map(|e| get_gen_method_name(&e.sig.ident))
-        .collect();
 
-    // for the same set of generated methods, create the mixedCase name for the JS side.
-    let js_names: Vec<Literal> = impl_tree
-        .methods
+    // a part without its own `rename_all` uses the one of the main `impl_block`.
+    let naming = match (&impl_args.part, impl_args.rename_all) {
+        (Some(_), None) => JsNaming::Main,
+        (_, rename_all) => JsNaming::Rule(rename_all),
+    };
+
+    for method in impl_tree.methods.iter().filter(|e| e.is_virtual()) {
+        if impl_args.remote.is_some() {
+            panic!(
+                "The method '{}' can't be virtual since a 'remote' impl_block can't be extended.",
+                method.method.sig.ident
+            );
+        }
+        let fnct: proc_macro::TokenStream = virtual_method_tok(method, naming).into();
+        impl_ast
+            .items
+            .push(ImplItem::Method(parse_macro_input!(fnct as ImplItemMethod)));
+    }
+
+    let exported_consts = match decorated_items
         .iter()
-        .map(|e| {
-            let js_name = format!("{}", &e.sig.ident).to_mixed_case();
-            Literal::string(&js_name)
+        .filter_map(|item| match item {
+            ImplItem::Const(c) => ExportedConst::new(c).transpose(),
+            _ => None,
         })
-        .collect();
+        .collect::<syn::Result<Vec<ExportedConst>>>()
+    {
+        Ok(consts) => consts,
+        Err(e) => return e.to_compile_error().into(),
+    };
+
+    // a part only adds the functions that set its items on the class, the main `impl_block` has
+    // the rest.
+    if let Some(part) = &impl_args.part {
+        if let Some(constructor) = impl_tree
+            .constructor
+            .as_ref()
+            .or(impl_tree.async_constructor.as_ref())
+        {
+            panic!(
+                "The constructor '{}' of struct {} must be in the main 'impl_block', not in the part '{}'.",
+                constructor.method.sig.ident, struct_name, part
+            );
+        }
+        for fnct in impl_part_fns(part, &impl_tree, &exported_consts, naming) {
+            let fnct: proc_macro::TokenStream = fnct.into();
+            impl_ast
+                .items
+                .push(ImplItem::Method(parse_macro_input!(fnct as ImplItemMethod)));
+        }
+
+        return quote! {
+            #trait_impl
+            #impl_ast
+            #(#mutable_errors)*
+        }
+        .into();
+    }
+
+    if !impl_args.boxed.is_mutable() {
+        if let Some(m) = impl_tree
+            .exported_methods()
+            .find(|m| utils::takes_mut_self(&m.sig.inputs))
+        {
+            panic!(
+                "The method '{}' takes '&mut self' but the 'impl_block' for struct {} is not mutable.\n\
+            To fix it, use '#[neon_class(impl_block, mutable)]' or take '&self' instead.",
+                m.sig.ident, struct_name
+            );
+        }
+    }
+
+    if !impl_args.boxed.is_shared() {
+        if let Some(m) = impl_tree
+            .exported_methods()
+            .find(|m| m.is_async() && m.has_receiver())
+        {
+            panic!(
+                "The async method '{}' takes 'self' but the 'impl_block' for struct {} is not shared.\n\
+            To fix it, use '#[neon_class(impl_block, shared)]' or '#[neon_class(impl_block, mutable = \"Mutex\")]'.",
+                m.sig.ident, struct_name
+            );
+        }
+        if let Some(m) = impl_tree
+            .methods
+            .iter()
+            .find(|m| m.is_blocking() && m.method.has_receiver())
+        {
+            panic!(
+                "The blocking method '{}' takes 'self' but the 'impl_block' for struct {} is not shared.\n\
+            To fix it, use '#[neon_class(impl_block, shared)]' or '#[neon_class(impl_block, mutable = \"Mutex\")]'.",
+                m.method.sig.ident, struct_name
+            );
+        }
+    }
+
+    if impl_args.extendable {
+        if let Some(m) = impl_tree
+            .methods
+            .iter()
+            .find(|m| (m.method.is_async() || m.is_blocking()) && m.method.has_receiver())
+        {
+            panic!(
+                "The method '{}' of struct {} runs after the JS call returns, this isn't supported by an 'extendable' impl_block.\n\
+            To fix it, remove 'extendable' or make it a static method.",
+                m.method.sig.ident, struct_name
+            );
+        }
+    }
+
+    // adds the helpers used by the generated methods to get to `Self` from the JS `this`.
+    // a remote type is stored in a `Remote` since `Finalize` can't be implemented for it.
+    let (object_ty, inner_ty, inner_obj) = match &impl_args.remote {
+        Some(remote) => (
+            quote! { #remote },
+            quote! { neon_class_runtime::remote::Remote<#remote> },
+            quote! { neon_class_runtime::remote::Remote(obj) },
+        ),
+        None => (quote! { Self }, quote! { Self }, quote! { obj }),
+    };
+    let boxed_type = impl_args.boxed.boxed_type(&inner_ty);
+    let wrapped_obj = impl_args.boxed.wrap(&inner_obj);
+    let mut helper_fns = vec![quote! {
+        #[doc(hidden)]
+        pub fn __neon_box<'a, C: neon::prelude::Context<'a>>(cx: &mut C, obj: #object_ty) -> neon::prelude::Handle<'a, neon::prelude::JsBox<#boxed_type>> {
+            cx.boxed(#wrapped_obj)
+        }
+    }];
+    // the instances of an extendable class might be instances of a derived class, so the JS
+    // `this` gets the struct either from its own box or through the upcaster of the derived class.
+    let this_ty = if impl_args.extendable {
+        helper_fns.push(quote! {
+            #[doc(hidden)]
+            pub fn __neon_this_of<'a>(cx: &mut neon::prelude::FunctionContext<'a>, obj: neon::prelude::Handle<'a, neon::prelude::JsObject>) -> neon::prelude::NeonResult<neon_class_runtime::inherit::This<'a, #boxed_type, Self>> {
+                use neon::prelude::Object;
+
+                let this = obj.get(cx, Self::THIS)?;
+                if let Ok(this) = this.downcast::<neon::prelude::JsBox<#boxed_type>, _>(cx) {
+                    return Ok(neon_class_runtime::inherit::This::Own(this));
+                }
+                let upcaster = this
+                    .downcast_or_throw::<neon::prelude::JsBox<neon_class_runtime::inherit::Upcaster<Self>>, _>(cx)?;
+                let this = (upcaster.0)(cx, obj)?;
+                Ok(neon_class_runtime::inherit::This::Derived(this))
+            }
+        });
+        quote! { neon_class_runtime::inherit::This<'a, #boxed_type, Self> }
+    } else {
+        helper_fns.push(quote! {
+            #[doc(hidden)]
+            pub fn __neon_this_of<'a>(cx: &mut neon::prelude::FunctionContext<'a>, obj: neon::prelude::Handle<'a, neon::prelude::JsObject>) -> neon::prelude::JsResult<'a, neon::prelude::JsBox<#boxed_type>> {
+                use neon::prelude::Object;
+
+                obj.get(cx, Self::THIS)?
+                    .downcast_or_throw::<neon::prelude::JsBox<#boxed_type>, _>(cx)
+            }
+        });
+        quote! { neon::prelude::Handle<'a, neon::prelude::JsBox<#boxed_type>> }
+    };
+    helper_fns.push(quote! {
+        #[doc(hidden)]
+        pub fn __neon_this<'a>(cx: &mut neon::prelude::FunctionContext<'a>) -> neon::prelude::NeonResult<#this_ty> {
+            let this = cx.this();
+            Self::__neon_this_of(cx, this)
+        }
+    });
+    // with `extendable`, `__neon_borrow` and `__neon_borrow_mut` pick between the box and the
+    // upcaster and these ones only borrow from the box.
+    let (borrow_fn, borrow_mut_fn) = if impl_args.extendable {
+        (
+            format_ident!("__neon_borrow_own"),
+            format_ident!("__neon_borrow_own_mut"),
+        )
+    } else {
+        (
+            format_ident!("__neon_borrow"),
+            format_ident!("__neon_borrow_mut"),
+        )
+    };
+    match impl_args.boxed {
+        BoxedKind::Plain => helper_fns.push(quote! {
+            #[doc(hidden)]
+            pub fn #borrow_fn(this: &neon::prelude::JsBox<#inner_ty>) -> Result<&#inner_ty, String> {
+                Ok(&**this)
+            }
+        }),
+        BoxedKind::RefCell => helper_fns.extend([
+            quote! {
+                #[doc(hidden)]
+                pub fn #borrow_fn(this: &neon::prelude::JsBox<std::cell::RefCell<#inner_ty>>) -> Result<std::cell::Ref<'_, #inner_ty>, String> {
+                    this.try_borrow()
+                        .map_err(|e| format!("Failed to borrow {}: {}", #struct_name, e))
+                }
+            },
+            quote! {
+                #[doc(hidden)]
+                pub fn #borrow_mut_fn(this: &neon::prelude::JsBox<std::cell::RefCell<#inner_ty>>) -> Result<std::cell::RefMut<'_, #inner_ty>, String> {
+                    this.try_borrow_mut()
+                        .map_err(|e| format!("Failed to borrow {}: {}", #struct_name, e))
+                }
+            },
+        ]),
+        BoxedKind::Shared => helper_fns.extend([
+            quote! {
+                #[doc(hidden)]
+                pub fn #borrow_fn(this: &neon::prelude::JsBox<std::sync::Arc<#inner_ty>>) -> Result<&#inner_ty, String> {
+                    Ok(&***this)
+                }
+            },
+            quote! {
+                #[doc(hidden)]
+                pub fn __neon_borrow_shared(this: &std::sync::Arc<#inner_ty>) -> Result<&#inner_ty, String> {
+                    Ok(&**this)
+                }
+            },
+            quote! {
+                #[doc(hidden)]
+                pub async fn __neon_borrow_shared_async(this: &std::sync::Arc<#inner_ty>) -> Result<&#inner_ty, String> {
+                    Ok(&**this)
+                }
+            },
+        ]),
+        BoxedKind::Mutex => helper_fns.extend([
+            quote! {
+                #[doc(hidden)]
+                pub fn #borrow_fn(this: &neon::prelude::JsBox<std::sync::Arc<neon_class_runtime::sync::Mutex<#inner_ty>>>) -> Result<neon_class_runtime::sync::MutexGuard<'_, #inner_ty>, String> {
+                    // the JS thread never waits for the lock, it may be the one holding it.
+                    this.try_lock().map_err(|e| match e {
+                        std::sync::TryLockError::WouldBlock => {
+                            format!("Failed to borrow {}: already borrowed", #struct_name)
+                        }
+                        std::sync::TryLockError::Poisoned(e) => {
+                            format!("Failed to lock {}: {}", #struct_name, e)
+                        }
+                    })
+                }
+            },
+            quote! {
+                #[doc(hidden)]
+                pub fn #borrow_mut_fn(this: &neon::prelude::JsBox<std::sync::Arc<neon_class_runtime::sync::Mutex<#inner_ty>>>) -> Result<neon_class_runtime::sync::MutexGuard<'_, #inner_ty>, String> {
+                    Self::#borrow_fn(this)
+                }
+            },
+            quote! {
+                #[doc(hidden)]
+                pub fn __neon_borrow_shared(this: &std::sync::Arc<neon_class_runtime::sync::Mutex<#inner_ty>>) -> Result<neon_class_runtime::sync::MutexGuard<'_, #inner_ty>, String> {
+                    this.lock()
+                        .map_err(|e| format!("Failed to lock {}: {}", #struct_name, e))
+                }
+            },
+            quote! {
+                #[doc(hidden)]
+                pub fn __neon_borrow_shared_mut(this: &std::sync::Arc<neon_class_runtime::sync::Mutex<#inner_ty>>) -> Result<neon_class_runtime::sync::MutexGuard<'_, #inner_ty>, String> {
+                    Self::__neon_borrow_shared(this)
+                }
+            },
+            quote! {
+                #[doc(hidden)]
+                pub async fn __neon_borrow_shared_async(this: &std::sync::Arc<neon_class_runtime::sync::Mutex<#inner_ty>>) -> Result<neon_class_runtime::sync::MutexGuard<'_, #inner_ty>, String> {
+                    this.lock_async()
+                        .await
+                        .map_err(|e| format!("Failed to lock {}: {}", #struct_name, e))
+                }
+            },
+            quote! {
+                #[doc(hidden)]
+                pub async fn __neon_borrow_shared_mut_async(this: &std::sync::Arc<neon_class_runtime::sync::Mutex<#inner_ty>>) -> Result<neon_class_runtime::sync::MutexGuard<'_, #inner_ty>, String> {
+                    Self::__neon_borrow_shared_async(this).await
+                }
+            },
+        ]),
+    }
+    if impl_args.extendable {
+        let guard_type = impl_args.boxed.guard_type(false);
+        helper_fns.push(quote! {
+            #[doc(hidden)]
+            pub fn __neon_borrow<'b>(this: &'b neon_class_runtime::inherit::This<'_, #boxed_type, Self>) -> Result<neon_class_runtime::inherit::Borrowed<'b, #guard_type, Self>, String> {
+                match this {
+                    neon_class_runtime::inherit::This::Own(this) => Self::__neon_borrow_own(this)
+                        .map(neon_class_runtime::inherit::Borrowed::Own),
+                    neon_class_runtime::inherit::This::Derived(this) => this.borrow()
+                        .map(neon_class_runtime::inherit::Borrowed::Derived),
+                }
+            }
+        });
+        if impl_args.boxed.is_mutable() {
+            let guard_type = impl_args.boxed.guard_type(true);
+            helper_fns.push(quote! {
+                #[doc(hidden)]
+                pub fn __neon_borrow_mut<'b>(this: &'b neon_class_runtime::inherit::This<'_, #boxed_type, Self>) -> Result<neon_class_runtime::inherit::BorrowedMut<'b, #guard_type, Self>, String> {
+                    match this {
+                        neon_class_runtime::inherit::This::Own(this) => Self::__neon_borrow_own_mut(this)
+                            .map(neon_class_runtime::inherit::BorrowedMut::Own),
+                        neon_class_runtime::inherit::This::Derived(this) => this.borrow_mut()
+                            .map(neon_class_runtime::inherit::BorrowedMut::Derived),
+                    }
+                }
+            });
+        }
+    }
+    // a derived class borrows its base with `AsRef`, and `AsMut` if it's mutable, so the methods
+    // of the base can be called on its instances.
+    if let Some(base) = &impl_args.extends {
+        helper_fns.push(quote! {
+            #[doc(hidden)]
+            pub fn __neon_borrow_as_base<'a, 'b>(this: &'b #this_ty) -> Result<Box<dyn std::ops::Deref<Target = #base> + 'b>, String> {
+                let this = Self::__neon_borrow(this)?;
+                Ok(Box::new(neon_class_runtime::inherit::MapRef::new(this)))
+            }
+        });
+        if impl_args.boxed.is_mutable() {
+            helper_fns.push(quote! {
+                #[doc(hidden)]
+                pub fn __neon_borrow_as_base_mut<'a, 'b>(this: &'b #this_ty) -> Result<Box<dyn std::ops::DerefMut<Target = #base> + 'b>, String> {
+                    let this = Self::__neon_borrow_mut(this)?;
+                    Ok(Box::new(neon_class_runtime::inherit::MapMut::new(this)))
+                }
+            });
+        } else {
+            let base_name = Literal::string(&quote!(#base).to_string().replace(' ', ""));
+            helper_fns.push(quote! {
+                #[doc(hidden)]
+                pub fn __neon_borrow_as_base_mut<'a, 'b>(_this: &'b #this_ty) -> Result<Box<dyn std::ops::DerefMut<Target = #base> + 'b>, String> {
+                    Err(format!("Can't borrow {} mutably from {}, its impl_block is not mutable", #base_name, #struct_name))
+                }
+            });
+        }
+        helper_fns.push(quote! {
+            #[doc(hidden)]
+            pub fn __neon_upcast<'a>(cx: &mut neon::prelude::FunctionContext<'a>, obj: neon::prelude::Handle<'a, neon::prelude::JsObject>) -> neon::prelude::NeonResult<Box<dyn neon_class_runtime::inherit::Upcast<#base> + 'a>> {
+                let this = Self::__neon_this_of(cx, obj)?;
+                Ok(Box::new(neon_class_runtime::inherit::Upcasted::new(
+                    this,
+                    Self::__neon_borrow_as_base,
+                    Self::__neon_borrow_as_base_mut,
+                )))
+            }
+        });
+    }
+    if impl_args.boxed.is_shared() {
+        // used by `async` methods to keep `Self` alive until their future completes.
+        helper_fns.push(quote! {
+            #[doc(hidden)]
+            pub fn __neon_share(this: &neon::prelude::JsBox<#boxed_type>) -> #boxed_type {
+                std::sync::Arc::clone(&**this)
+            }
+        });
+    }
+    // the parts expand on their own, this tells the ones without a `rename_all` how the class names
+    // its items.
+    if !impl_args.parts.is_empty() {
+        let rename_all = match impl_args.rename_all {
+            Some(rule) => {
+                let value = rule.value();
+                quote! { Some(#value) }
+            }
+            None => quote! { None },
+        };
+        let rename_all_const: proc_macro::TokenStream = quote! {
+            #[doc(hidden)]
+            #[allow(dead_code)]
+            pub(crate) const __NEON_RENAME_ALL: Option<&'static str> = #rename_all;
+        }
+        .into();
+        let rename_all_const = parse_macro_input!(rename_all_const as ImplItemConst);
+        impl_ast.items.push(ImplItem::Const(rename_all_const));
+    }
+    let (derive_js_fns, derive_js_prototype_tok, derive_js_constructor_tok) =
+        derive_js_tok(&impl_args.derive_js, &object_ty);
+    let (events_fns, events_prototype_tok) = match &impl_args.events {
+        Some(field) => events_tok(&object_ty, field),
+        None => (Vec::new(), quote! {}),
+    };
+    for fnct in helper_fns
+        .into_iter()
+        .chain(derive_js_fns)
+        .chain(events_fns)
+    {
+        let fnct: proc_macro::TokenStream = fnct.into();
+        impl_ast
+            .items
+            .push(ImplItem::Method(parse_macro_input!(fnct as ImplItemMethod)));
+    }
+    if impl_tree.constructor.is_none()
+        && impl_tree.async_constructor.is_none()
+        && (!impl_tree.static_methods.is_empty()
+            || !exported_consts.is_empty()
+            || impl_args.derive_js.iter().any(|derived| derived == "Ord"))
+    {
+        panic!(
+            "Found static methods or constants for struct {} but no constructor to export them on.\n\
+        To fix it, decorate one of the methods with '#[neon_class(constructor)]'.",
+            struct_name
+        );
+    }
+
+    // without a constructor, an async constructor still needs a class to be exported on.
+    let exported_ctor = impl_tree
+        .constructor
+        .as_ref()
+        .or(impl_tree.async_constructor.as_ref());
+    // the class can be renamed from the `impl_block` or from the constructor, if neither is given
+    // the struct's name is used. The `js_name` of an async constructor is its own name.
+    let ctor_js_name = exported_ctor.and_then(|constructor| {
+        constructor
+            .js_name
+            .as_ref()
+            .filter(|_| constructor.is_constructor())
+    });
+    let exported_name = match (&impl_args.js_name, ctor_js_name) {
+        (Some(class_name), Some(ctor_name)) if class_name != ctor_name => panic!(
+            "The struct {} is exported as '{}' by the 'impl_block' but as '{}' by the constructor '{}'.\n\
+        To fix it, remove one of the two 'js_name' args.",
+            struct_name,
+            class_name,
+            ctor_name,
+            exported_ctor.unwrap().method.sig.ident
+        ),
+        (Some(js_name), _) | (None, Some(js_name)) => js_name.clone(),
+        (None, None) => struct_name_as_str.clone(),
+    };
+
+    // a derived prototype holds the upcaster of its base and inherits from a prototype of the base.
+    // The registered class inherits from the exported base class instead, see `register_fn`.
+    let base_setup_tok = impl_args.extends.as_ref().map(|base| {
+        let set_prototype_of_tok = object_set_prototype_of_tok();
+        quote! {
+            let upcaster = cx.boxed(neon_class_runtime::inherit::Upcaster::<#base>(Self::__neon_upcast));
+            prototype.set(cx, <#base as neon_class_runtime::inherit::Extendable>::THIS, upcaster)?;
+
+            let base_prototype = neon::prelude::JsObject::new(cx);
+            <#base>::__neon_setup_prototype(cx, base_prototype)?;
+            #set_prototype_of_tok
+            set_prototype_of.call(cx, object_ctor, [
+                prototype.upcast::<neon::prelude::JsValue>(),
+                base_prototype.upcast(),
+            ])?;
+        }
+    });
+
+    // so `Object.prototype.toString` and node's `util.inspect` show the name of the class.
+    let to_string_tag_tok = {
+        let symbol_tok = symbol_tok(&SymbolKey::WellKnown("toStringTag".to_string()));
+        let define_property_tok = object_define_property_tok();
+        let exported_name = Literal::string(&exported_name);
+        quote! {
+            #symbol_tok
+            #define_property_tok
+            let descriptor = neon::prelude::JsObject::new(cx);
+            let tag = neon::prelude::JsString::new(cx, #exported_name);
+            descriptor.set(cx, "value", tag)?;
+            let configurable = neon::prelude::JsBoolean::new(cx, true);
+            descriptor.set(cx, "configurable", configurable)?;
+            define_property.call(cx, object_ctor, [
+                prototype.upcast::<neon::prelude::JsValue>(),
+                key,
+                descriptor.upcast(),
+            ])?;
+        }
+    };
+
+    let prototype_items_tok = prototype_items_tok(&impl_tree, naming);
+    let part_prototype_fns = impl_args
+        .parts
+        .iter()
+        .map(|part| format_ident!("__neon_setup_prototype_{}", part));
 
     // setup the prototype object based on the decorated methods.
+    let setup_prototype_fn = {
+        let fnct = quote! {
+            #[doc(hidden)]
+            pub fn __neon_setup_prototype<'a, C: neon::prelude::Context<'a>>(cx: &mut C, prototype: neon::prelude::Handle<'a, neon::prelude::JsObject>) -> neon::prelude::NeonResult<()> {
+                use neon::prelude::Object;
+
+                #derive_js_prototype_tok
+
+                #events_prototype_tok
+
+                #prototype_items_tok
+
+                #(Self::#part_prototype_fns(cx, prototype)?;)*
+
+                #base_setup_tok
+
+                #to_string_tag_tok
+
+                Ok(())
+            }
+        };
+        let fnct: proc_macro::TokenStream = fnct.into();
+        parse_macro_input!(fnct as ImplItemMethod)
+    };
+    impl_ast.items.push(ImplItem::Method(setup_prototype_fn));
+
     let prototype_setup_tok = quote! {
         use neon::prelude::Object;
 
         let prototype = constructor
             .get(cx, "prototype")?
             .downcast_or_throw::<neon::prelude::JsObject, _>(cx)?;
-
-        #(
-            let f = neon::prelude::JsFunction::new(cx, Self::#gen_method_names)?;
-            prototype.set(cx, #js_names, f)?;
-        )*
+        Self::__neon_setup_prototype(cx, prototype)?;
     };
 
-    if let Some(constructor) = &impl_tree.constructor {
-        let orig_ctor_name = &constructor.sig.ident;
-        let gen_ctor_name = get_gen_method_name(orig_ctor_name);
+    if let Some(constructor) = exported_ctor {
+        let orig_ctor_name = &constructor.method.sig.ident;
         let register_fn_name = format_ident!("register_{}", orig_ctor_name);
 
+        let js_constructor_tok = if constructor.is_constructor() {
+            let gen_ctor_name = get_gen_method_name(orig_ctor_name);
+            quote! {
+                neon::prelude::JsFunction::new(cx, Self::#gen_ctor_name)?
+            }
+        } else {
+            let msg = Literal::string(&format!(
+                "{0} can't be constructed with 'new', use '{0}.{1}(...)' instead",
+                exported_name,
+                constructor.js_name(impl_args.rename_all)
+            ));
+            quote! {
+                neon::prelude::JsFunction::new(cx, |mut cx| {
+                    cx.throw_type_error::<_, neon::prelude::Handle<neon::prelude::JsUndefined>>(#msg)
+                })?
+            }
+        };
+
+        let exported_name = Literal::string(&exported_name);
+        let constructor_items_tok = constructor_items_tok(&impl_tree, &exported_consts, naming);
+        let part_constructor_fns = impl_args
+            .parts
+            .iter()
+            .map(|part| format_ident!("__neon_setup_constructor_{}", part));
+
+        // the base class has to be exported already so the derived class can inherit from it.
+        let extends_tok = impl_args.extends.as_ref().map(|base| {
+            let set_prototype_of_tok = object_set_prototype_of_tok();
+            quote! {
+                let base_name = <#base as neon_class_runtime::inherit::Extendable>::JS_NAME;
+                let base_constructor = cx.exports_object()?.get(cx, base_name)?;
+                let base_constructor = match base_constructor.downcast::<neon::prelude::JsFunction, _>(cx) {
+                    Ok(base_constructor) => base_constructor,
+                    Err(_) => {
+                        return cx.throw_error(format!("{} must be registered before {}", base_name, #exported_name))
+                    }
+                };
+                let base_prototype = base_constructor.get(cx, "prototype")?;
+
+                #set_prototype_of_tok
+                set_prototype_of.call(cx, object_ctor, [
+                    prototype.upcast::<neon::prelude::JsValue>(),
+                    base_prototype,
+                ])?;
+                set_prototype_of.call(cx, object_ctor, [
+                    constructor.upcast::<neon::prelude::JsValue>(),
+                    base_constructor.upcast(),
+                ])?;
+            }
+        });
+
         let register_fn = {
             let fnct = quote! {
                 /// Expose the constructor for this object to the JS side.
                 pub fn #register_fn_name(cx: &mut neon::prelude::ModuleContext) -> neon::prelude::NeonResult<()> {
-                    let constructor = neon::prelude::JsFunction::new(cx, Self::#gen_ctor_name)?;
+                    let constructor = #js_constructor_tok;
 
                     #prototype_setup_tok
 
-                    cx.export_value(#struct_name, constructor)?;
+                    #extends_tok
+
+                    #derive_js_constructor_tok
+
+                    #constructor_items_tok
+
+                    #(Self::#part_constructor_fns(cx, constructor)?;)*
+
+                    cx.export_value(#exported_name, constructor)?;
+
+                    // kept so `to_js_obj` makes instances of this class.
+                    let constructor = constructor.root(cx);
+                    if let Some(previous) = Self::__neon_with_constructor(|class| class.borrow_mut().replace(constructor)) {
+                        previous.drop(cx);
+                    }
                     Ok(())
                 }
             };
@@ -370,21 +1930,57 @@ fn impl_block(_args: TokenStream, input: TokenStream) -> TokenStream {
         impl_ast.items.push(ImplItem::Method(register_fn));
     }
 
+    let with_constructor_fn = {
+        let fnct = quote! {
+            /// Gives `f` the constructor the class was registered with on the current JS thread.
+            #[doc(hidden)]
+            pub fn __neon_with_constructor<R>(f: impl FnOnce(&std::cell::RefCell<Option<neon::prelude::Root<neon::prelude::JsFunction>>>) -> R) -> R {
+                std::thread_local! {
+                    // the main thread and each worker register their own class.
+                    static CONSTRUCTOR: std::cell::RefCell<Option<neon::prelude::Root<neon::prelude::JsFunction>>> =
+                        const { std::cell::RefCell::new(None) };
+                }
+                CONSTRUCTOR.with(f)
+            }
+        };
+        let fnct: proc_macro::TokenStream = fnct.into();
+        parse_macro_input!(fnct as ImplItemMethod)
+    };
+    impl_ast.items.push(ImplItem::Method(with_constructor_fn));
+
+    let object_create_tok = object_create_tok();
     let to_js_obj_fn = {
         let fnct = quote! {
             /// Turn an object of `Self` into a JS object.
             ///
             /// See example usage in [impl_block](macro@neon_class_macros::impl_block#to_js_obj).
-            pub fn to_js_obj<'a, 'b>(cx: &'b mut impl neon::prelude::Context<'a>, obj: Self) -> neon::prelude::JsResult<'a, neon::prelude::JsObject> {
+            pub fn to_js_obj<'a, 'b>(cx: &'b mut impl neon::prelude::Context<'a>, obj: #object_ty) -> neon::prelude::JsResult<'a, neon::prelude::JsObject> {
+                // once registered, the object is an instance of the exported class.
+                let registered = Self::__neon_with_constructor(|class| {
+                    class.borrow().as_ref().map(|constructor| constructor.to_inner(cx))
+                });
+                if let Some(constructor) = registered {
+                    use neon::prelude::Object;
+
+                    let prototype = constructor.get(cx, "prototype")?;
+                    #object_create_tok
+                    let this = object_create
+                        .call(cx, object_ctor, [prototype])?
+                        .downcast_or_throw::<neon::prelude::JsObject, _>(cx)?;
+                    let handle = Self::__neon_box(cx, obj);
+                    this.set(cx, Self::THIS, handle)?;
+                    return Ok(this);
+                }
+
                 let constructor = neon::prelude::JsFunction::new(cx, |mut cx| {
-                    let this = cx.argument::<neon::prelude::JsBox<Self>>(0)?;
+                    let this = cx.argument::<neon::prelude::JsBox<#boxed_type>>(0)?;
                     cx.this().set(&mut cx, Self::THIS, this)?;
                     Ok(cx.undefined())
                 })?;
 
                 #prototype_setup_tok
 
-                let handle = cx.boxed(obj);
+                let handle = Self::__neon_box(cx, obj);
                 let c = constructor.construct(cx, [handle])?;
                 Ok(c)
             }
@@ -394,8 +1990,23 @@ fn impl_block(_args: TokenStream, input: TokenStream) -> TokenStream {
     };
     impl_ast.items.push(ImplItem::Method(to_js_obj_fn));
 
+    let extendable_impl = if impl_args.extendable {
+        let self_ty = &impl_ast.self_ty;
+        let exported_name = Literal::string(&exported_name);
+        quote! {
+            impl neon_class_runtime::inherit::Extendable for #self_ty {
+                const JS_NAME: &'static str = #exported_name;
+                const THIS: &'static str = Self::THIS;
+            }
+        }
+    } else {
+        quote! {}
+    };
+
     let tokens = quote! {
+        #trait_impl
         #impl_ast
+        #extendable_impl
     };
 
     tokens.into()
@@ -1,11 +1,13 @@
 //! Utility functions to help deal with converting from [`neon::types`] to supported rust types and vice versa.
-use proc_macro2::{Ident, Literal, TokenStream};
-use quote::{format_ident, quote};
+use heck::{CamelCase, MixedCase, ShoutySnakeCase, SnakeCase};
+use proc_macro2::{Ident, Literal, TokenStream, TokenTree};
+use quote::{format_ident, quote, ToTokens};
 use syn::punctuated::Punctuated;
 use syn::token::Comma;
 use syn::{
-    FnArg, GenericArgument, ImplItemMethod, ItemFn, Lifetime, Meta, NestedMeta, Pat, PathArguments,
-    PathSegment, ReturnType, Type, TypePath,
+    Attribute, FnArg, GenericArgument, ImplItem, ImplItemConst, ImplItemMethod, ItemFn, ItemImpl,
+    Lifetime, Lit, Meta, NestedMeta, Pat, PathArguments, PathSegment, ReturnType, Type,
+    TypeParamBound, TypePath,
 };
 
 pub(crate) trait AnnotatedFn {
@@ -13,6 +15,9 @@ pub(crate) trait AnnotatedFn {
     fn get_ret_type(&self) -> &syn::ReturnType;
     fn inputs(&self) -> &Punctuated<FnArg, Comma>;
     fn is_method(&self) -> bool;
+    /// Checks if the function takes `self` in any form.
+    fn has_receiver(&self) -> bool;
+    fn is_async(&self) -> bool;
 }
 
 impl AnnotatedFn for ImplItemMethod {
@@ -31,6 +36,14 @@ impl AnnotatedFn for ImplItemMethod {
     fn is_method(&self) -> bool {
         true
     }
+
+    fn has_receiver(&self) -> bool {
+        self.sig.receiver().is_some()
+    }
+
+    fn is_async(&self) -> bool {
+        self.sig.asyncness.is_some()
+    }
 }
 
 impl AnnotatedFn for ItemFn {
@@ -49,6 +62,14 @@ impl AnnotatedFn for ItemFn {
     fn is_method(&self) -> bool {
         false
     }
+
+    fn has_receiver(&self) -> bool {
+        false
+    }
+
+    fn is_async(&self) -> bool {
+        self.sig.asyncness.is_some()
+    }
 }
 
 fn is_native_numeric(arg_type: &Ident) -> bool {
@@ -59,6 +80,26 @@ fn is_neon_handle(arg_type: &Ident) -> bool {
     arg_type == "Handle"
 }
 
+/// Checks for the `neon_class_runtime::AbortToken` arg that takes the `AbortSignal` of a call.
+fn is_abort_token(arg_type: &TypePath) -> bool {
+    arg_type
+        .path
+        .segments
+        .last()
+        .map(|se| se.ident == "AbortToken")
+        .unwrap_or(false)
+}
+
+/// Checks for an arg of the given `neon_class_runtime` type, i.e. a `ThreadsafeCallback<T>`.
+fn is_runtime_type(arg_type: &TypePath, name: &str) -> bool {
+    arg_type
+        .path
+        .segments
+        .last()
+        .map(|se| se.ident == name)
+        .unwrap_or(false)
+}
+
 fn extract_neon_handle_type(arg_type: &PathSegment) -> &TypePath {
     if let PathArguments::AngleBracketed(a) = &arg_type.arguments {
         if let GenericArgument::Type(Type::Path(p)) =
@@ -100,6 +141,7 @@ pub fn parse_rust_fn_args(
                 }
                 match fn_arg.ty.as_ref() {
                     Type::Path(tp) => Some(extract_from_native_input_type(idx - idx_adjuster, tp)),
+                    ty if is_writer(ty) => Some(extract_writer(idx - idx_adjuster)),
                     _ => None,
                 }
             }
@@ -115,6 +157,57 @@ pub fn parse_rust_fn_args(
     (parsed_args.iter().cloned().unzip(), cx_is_arg)
 }
 
+/// Finds the ident given by [`parse_rust_fn_args`] to the `AbortToken` arg, if any.
+///
+/// Panics if the `AbortToken` is not the last arg since the `AbortSignal` is a trailing arg on the
+/// JS side.
+pub fn abort_token_arg(
+    input_args: &Punctuated<FnArg, Comma>,
+    arg_idents: &[Ident],
+) -> Option<Ident> {
+    let js_arg_types = input_args.iter().filter_map(|fn_arg| match fn_arg {
+        FnArg::Typed(fn_arg) => {
+            if let Pat::Ident(p_ident) = fn_arg.pat.as_ref() {
+                if p_ident.ident == "cx" || p_ident.ident == "_cx" {
+                    return None;
+                }
+            }
+            // the same args `parse_rust_fn_args` gives idents to.
+            match fn_arg.ty.as_ref() {
+                ty @ Type::Path(_) => Some(ty),
+                ty if is_writer(ty) => Some(ty),
+                _ => None,
+            }
+        }
+        FnArg::Receiver(_) => None,
+    });
+
+    let idx = js_arg_types
+        .clone()
+        .position(|ty| matches!(ty, Type::Path(tp) if is_abort_token(tp)))?;
+    if idx + 1 != js_arg_types.count() {
+        panic!("The 'AbortToken' must be the last arg since the 'AbortSignal' is the last arg on the JS side.");
+    }
+
+    Some(arg_idents[idx].clone())
+}
+
+/// Checks for an `impl Write` arg, which takes a node `Writable`.
+fn is_writer(ty: &Type) -> bool {
+    matches!(ty, Type::ImplTrait(_)) && trait_bound(ty, "Write").is_some()
+}
+
+fn extract_writer(arg_idx: usize) -> (Ident, TokenStream) {
+    let arg_ident = format_ident!("arg_{}", arg_idx);
+    let idx_literal = Literal::i32_unsuffixed(arg_idx as i32);
+    let tok = quote! {
+        let #arg_ident = cx.argument::<neon::prelude::JsObject>(#idx_literal)?;
+        let #arg_ident = neon_class_runtime::stream::JsWriter::new(&mut cx, #arg_ident);
+    };
+
+    (arg_ident, tok)
+}
+
 fn extract_from_native_input_type(arg_idx: usize, arg: &TypePath) -> (Ident, TokenStream) {
     let arg_ident = format_ident!("arg_{}", arg_idx);
     let idx_literal = Literal::i32_unsuffixed(arg_idx as i32);
@@ -134,6 +227,22 @@ fn extract_from_native_input_type(arg_idx: usize, arg: &TypePath) -> (Ident, Tok
         quote! {
             let #arg_ident = cx.argument::<#ty>(#idx_literal)?;
         }
+    } else if is_runtime_type(arg, "ThreadsafeCallback") {
+        quote! {
+            let #arg_ident = cx.argument::<neon::prelude::JsFunction>(#idx_literal)?;
+            let #arg_ident = neon_class_runtime::ThreadsafeCallback::new(&mut cx, #arg_ident);
+        }
+    } else if is_runtime_type(arg, "JsFn") {
+        quote! {
+            let #arg_ident = cx.argument::<neon::prelude::JsFunction>(#idx_literal)?;
+            let #arg_ident = neon_class_runtime::JsFn::new(#arg_ident);
+        }
+    } else if is_abort_token(arg) {
+        // the `AbortSignal` is optional on the JS side.
+        quote! {
+            let #arg_ident = cx.argument_opt(#idx_literal);
+            let #arg_ident = neon_class_runtime::abort::from_signal(&mut cx, #arg_ident)?;
+        }
     } else {
         quote! {
             let #arg_ident = cx.argument::<neon::prelude::JsValue>(#idx_literal)?;
@@ -144,7 +253,11 @@ fn extract_from_native_input_type(arg_idx: usize, arg: &TypePath) -> (Ident, Tok
     (arg_ident, tok)
 }
 
-type NativeResultParser = Option<fn(&Ident) -> proc_macro2::TokenStream>;
+/// Turns the result of the decorated method, bound to the given ident, into a `JsResult`.
+///
+/// The second arg is the expression for the `&mut` [`Context`](neon::prelude::Context) to use,
+/// i.e. `&mut cx` in a generated method.
+type NativeResultParser = Option<fn(&Ident, &TokenStream) -> proc_macro2::TokenStream>;
 
 /// This functions is in charge of determining if the return type provided by the decorated method needs
 /// to be modified or not.
@@ -166,7 +279,7 @@ pub fn parse_return_type(
             };
             return (
                 tok,
-                Some(|_ident| {
+                Some(|_ident, _cx| {
                     quote! {
                         Ok(cx.undefined())
                     }
@@ -174,6 +287,105 @@ pub fn parse_return_type(
             );
         }
         ReturnType::Type(_, ty) => {
+            // an `Iterator` is turned into a JS iterator that converts its items as JS pulls them.
+            if let Some(item) = trait_item(ty, "Iterator") {
+                let return_tok = quote! {
+                    -> neon::prelude::JsResult<#lifetime, neon::prelude::JsObject>
+                };
+
+                let parse_tok: NativeResultParser = if matches!(item, Type::Path(p) if p.path.is_ident("Self"))
+                {
+                    Some(|ident, cx_ref| {
+                        quote! {
+                            neon_class_runtime::iter::to_js_iterator(#cx_ref, #ident, |cx, item| {
+                                Self::to_js_obj(cx, item).map(|obj| obj.upcast())
+                            })
+                        }
+                    })
+                } else {
+                    Some(|ident, cx_ref| {
+                        quote! {
+                            neon_class_runtime::iter::to_js_iterator(#cx_ref, #ident, |cx, item| {
+                                neon_serde::to_value(cx, &item).map_err_into_throw(cx)
+                            })
+                        }
+                    })
+                };
+
+                return (return_tok, parse_tok);
+            }
+
+            // an `impl Read` is turned into a node `Readable`, read on another thread as JS
+            // consumes it.
+            if trait_bound(ty, "Read").is_some() {
+                let return_tok = quote! {
+                    -> neon::prelude::JsResult<#lifetime, neon::prelude::JsObject>
+                };
+                let parse_tok: NativeResultParser = Some(|ident, cx_ref| {
+                    quote! {
+                        neon_class_runtime::stream::to_readable(#cx_ref, #ident)
+                    }
+                });
+
+                return (return_tok, parse_tok);
+            }
+
+            // a `Stream` or a channel `Receiver` is turned into a JS async iterator, its items are
+            // pulled on another thread as JS awaits them.
+            let is_stream = trait_item(ty, "Stream").is_some();
+            if is_stream || is_receiver(ty) {
+                let return_tok = quote! {
+                    -> neon::prelude::JsResult<#lifetime, neon::prelude::JsObject>
+                };
+
+                let parse_tok: NativeResultParser = if is_stream {
+                    Some(|ident, cx_ref| {
+                        quote! {
+                            let #ident = neon_class_runtime::async_iter::FromStream::new(#ident);
+                            neon_class_runtime::async_iter::to_js_async_iterator(#cx_ref, #ident, |cx, item| {
+                                neon_serde::to_value(cx, &item).map_err_into_throw(cx)
+                            })
+                        }
+                    })
+                } else {
+                    Some(|ident, cx_ref| {
+                        quote! {
+                            neon_class_runtime::async_iter::to_js_async_iterator(#cx_ref, #ident, |cx, item| {
+                                neon_serde::to_value(cx, &item).map_err_into_throw(cx)
+                            })
+                        }
+                    })
+                };
+
+                return (return_tok, parse_tok);
+            }
+
+            // `Self` can't go through `neon_serde`, it gets turned into a JS object instead.
+            if let Some(is_result) = returns_self(ty) {
+                let return_tok = quote! {
+                    -> neon::prelude::JsResult<#lifetime, neon::prelude::JsObject>
+                };
+
+                let parse_tok: NativeResultParser = if is_result {
+                    Some(|ident, cx_ref| {
+                        quote! {
+                            let #ident = #ident.map_err(|e| {
+                                cx.throw_error::<_, ()>(format!("{}", e)).unwrap_err()
+                            })?;
+                            Self::to_js_obj(#cx_ref, #ident)
+                        }
+                    })
+                } else {
+                    Some(|ident, cx_ref| {
+                        quote! {
+                            Self::to_js_obj(#cx_ref, #ident)
+                        }
+                    })
+                };
+
+                return (return_tok, parse_tok);
+            }
+
             if let Type::Path(path) = ty.as_ref() {
                 let native_method_return_type = &path.path.segments.last().unwrap().ident;
                 if native_method_return_type != "JsResult" {
@@ -182,19 +394,19 @@ pub fn parse_return_type(
                     };
 
                     let parse_tok: NativeResultParser = if throws_on_err {
-                        Some(|ident| {
+                        Some(|ident, cx_ref| {
                             quote! {
                                 let #ident = #ident.map_err(|e| {
                                     cx.throw_error::<_, ()>(format!("{}", e)).unwrap_err()
                                 })?;
-                                let #ident = neon_serde::to_value(&mut cx, &#ident).map_err_into_throw(&mut cx)?;
+                                let #ident = neon_serde::to_value(#cx_ref, &#ident).map_err_into_throw(#cx_ref)?;
                                 Ok(#ident)
                             }
                         })
                     } else {
-                        Some(|ident| {
+                        Some(|ident, cx_ref| {
                             quote! {
-                                let #ident = neon_serde::to_value(&mut cx, &#ident).map_err_into_throw(&mut cx)?;
+                                let #ident = neon_serde::to_value(#cx_ref, &#ident).map_err_into_throw(#cx_ref)?;
                                 Ok(#ident)
                             }
                         })
@@ -214,6 +426,449 @@ pub fn parse_return_type(
     (tok, None)
 }
 
+/// Tokens that turn the output of an `async` function, bound to `res`, into the value a promise is
+/// resolved with. They run inside [`Channel::settle_with`](neon::prelude::Channel::settle_with)
+/// where `cx` is a `&mut TaskContext`.
+///
+/// A `Result` rejects the promise with the display of its `Err`. `Self` is turned into a JS object
+/// with `to_js_obj` and everything else goes through `neon_serde`.
+pub fn parse_async_return_type(output: &ReturnType) -> TokenStream {
+    let returns_result = match output {
+        ReturnType::Default => {
+            return quote! {
+                Ok(cx.undefined().upcast::<neon::prelude::JsValue>())
+            };
+        }
+        ReturnType::Type(_, ty) => {
+            // `Self` can't go through `neon_serde`, it gets turned into a JS object instead.
+            if let Some(is_result) = returns_self(ty) {
+                let unwrap_result = if is_result {
+                    quote! {
+                        let res = res.map_err(|e| {
+                            cx.throw_error::<_, ()>(format!("{}", e)).unwrap_err()
+                        })?;
+                    }
+                } else {
+                    quote! {}
+                };
+                return quote! {
+                    #unwrap_result
+                    Self::to_js_obj(cx, res)
+                };
+            }
+            matches!(ty.as_ref(), Type::Path(p) if p.path.segments.last().unwrap().ident == "Result")
+        }
+    };
+
+    let unwrap_result = if returns_result {
+        quote! {
+            let res = res.map_err(|e| {
+                cx.throw_error::<_, ()>(format!("{}", e)).unwrap_err()
+            })?;
+        }
+    } else {
+        quote! {}
+    };
+
+    quote! {
+        #unwrap_result
+        neon_serde::to_value(cx, &res).map_err_into_throw(cx)
+    }
+}
+
+/// Checks if the type is `Self` or `Result<Self, E>`.
+///
+/// Returns `Some(true)` for the `Result` case, `Some(false)` for a plain `Self` and `None` otherwise.
+fn returns_self(ty: &Type) -> Option<bool> {
+    let is_self = |ty: &Type| matches!(ty, Type::Path(p) if p.path.is_ident("Self"));
+
+    if is_self(ty) {
+        return Some(false);
+    }
+
+    if let Type::Path(path) = ty {
+        let last = path.path.segments.last()?;
+        if last.ident == "Result" {
+            if let PathArguments::AngleBracketed(ab) = &last.arguments {
+                if let Some(GenericArgument::Type(ok_ty)) = ab.args.first() {
+                    if is_self(ok_ty) {
+                        return Some(true);
+                    }
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Finds the bound of the given trait name, i.e. `Iterator`, in an `impl Trait`, a
+/// `Box<dyn Trait>` or a `Pin<Box<dyn Trait>>`.
+fn trait_bound<'t>(ty: &'t Type, trait_name: &str) -> Option<&'t PathSegment> {
+    let bounds = match ty {
+        Type::ImplTrait(it) => &it.bounds,
+        Type::Path(path) => {
+            let last = path.path.segments.last()?;
+            match &last.arguments {
+                PathArguments::AngleBracketed(ab) if last.ident == "Box" => {
+                    match ab.args.first()? {
+                        GenericArgument::Type(Type::TraitObject(to)) => &to.bounds,
+                        _ => return None,
+                    }
+                }
+                PathArguments::AngleBracketed(ab) if last.ident == "Pin" => {
+                    return match ab.args.first()? {
+                        GenericArgument::Type(inner) => trait_bound(inner, trait_name),
+                        _ => None,
+                    };
+                }
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+
+    bounds.iter().find_map(|bound| match bound {
+        TypeParamBound::Trait(tb) => tb
+            .path
+            .segments
+            .last()
+            .filter(|last| last.ident == trait_name),
+        _ => None,
+    })
+}
+
+/// Gets the `Item` of a trait bound found by [`trait_bound`], i.e. `T` for
+/// `impl Iterator<Item = T>`.
+fn trait_item<'t>(ty: &'t Type, trait_name: &str) -> Option<&'t Type> {
+    match &trait_bound(ty, trait_name)?.arguments {
+        PathArguments::AngleBracketed(ab) => ab.args.iter().find_map(|arg| match arg {
+            GenericArgument::Binding(b) if b.ident == "Item" => Some(&b.ty),
+            _ => None,
+        }),
+        _ => None,
+    }
+}
+
+/// Checks if the type is a channel `Receiver<T>`, written as `std::sync::mpsc::Receiver<f64>`,
+/// `sync::mpsc::Receiver<f64>` or `mpsc::Receiver<f64>`. A bare `Receiver<T>` could be any other
+/// type of the same name, so it's left alone.
+fn is_receiver(ty: &Type) -> bool {
+    const PATH: [&str; 4] = ["std", "sync", "mpsc", "Receiver"];
+
+    if let Type::Path(path) = ty {
+        let segments = &path.path.segments;
+        let is_generic = segments
+            .last()
+            .is_some_and(|last| matches!(last.arguments, PathArguments::AngleBracketed(_)));
+        return is_generic
+            && (2..=PATH.len()).contains(&segments.len())
+            && PATH[PATH.len() - segments.len()..]
+                .iter()
+                .zip(segments)
+                .all(|(name, segment)| segment.ident == name);
+    }
+
+    false
+}
+
+/// An associated const decorated with `#[neon_class(constant, ...)]`.
+pub struct ExportedConst {
+    pub ident: Ident,
+    /// Name given with `js_name = "..."`, if any.
+    pub js_name: Option<String>,
+}
+
+impl ExportedConst {
+    pub fn new(item: &ImplItemConst) -> syn::Result<Option<Self>> {
+        Ok(parse_neon_class_attrs(&item.attrs)?
+            .filter(|attr| attr.main == "constant")
+            .map(|attr| ExportedConst {
+                ident: item.ident.clone(),
+                js_name: attr.js_name,
+            }))
+    }
+
+    /// Name of the constant on the JS side.
+    ///
+    /// Unless given with `js_name = "..."`, this is the rust name with the `rename_all` rule
+    /// applied. Without a `rename_all` rule the name is kept as is.
+    pub fn js_name(&self, rename_all: Option<RenameRule>) -> String {
+        self.js_name.clone().unwrap_or_else(|| {
+            rename_all
+                .unwrap_or(RenameRule::None)
+                .apply(&format!("{}", self.ident))
+        })
+    }
+}
+
+/// The contents of a `#[neon_class(...)]` attribute.
+struct NeonClassAttr {
+    main: String,
+    args: Vec<String>,
+    js_name: Option<String>,
+    symbol: Option<String>,
+}
+
+/// Finds and parses the `#[neon_class(...)]` attributes in `attrs`.
+///
+/// An unknown arg is an error spanned on that arg.
+fn parse_neon_class_attrs(attrs: &[Attribute]) -> syn::Result<Option<NeonClassAttr>> {
+    let mut parsed_attr = NeonClassAttr {
+        main: String::new(),
+        args: Vec::new(),
+        js_name: None,
+        symbol: None,
+    };
+
+    let mut neon_class_attribute_found = false;
+
+    for attrs in attrs {
+        // TODO fix this for renames. currently, not sure how to get if the macro was renamed
+        // at import. We use the macro name here to find the methods in the ast that were marked as
+        // 'constructor' or 'method'. See the rename_macro_error.rs test.
+        if !is_neon_class_attr(attrs) {
+            continue;
+        }
+        neon_class_attribute_found = true;
+
+        let m = attrs.parse_meta().unwrap();
+        match &m {
+            Meta::Path(path) => {
+                parsed_attr
+                    .args
+                    .push(format!("{}", path.segments.last().unwrap().ident));
+            }
+            Meta::List(meta_ls) => {
+                let main_arg = meta_ls.nested.first().unwrap();
+                match main_arg {
+                    NestedMeta::Meta(meta) => {
+                        if let Meta::Path(path) = meta {
+                            parsed_attr.main = format!("{}", path.segments.last().unwrap().ident);
+                        }
+                    }
+                    NestedMeta::Lit(_) => {}
+                }
+                // Skip 1 here since the first one is saved as the main
+                for nm in meta_ls.nested.iter().skip(1) {
+                    if let Some(js_name) = get_js_name(nm)? {
+                        parsed_attr.js_name = Some(js_name);
+                        continue;
+                    }
+                    if let Some(symbol) = get_symbol(nm)? {
+                        parsed_attr.symbol = Some(symbol);
+                        continue;
+                    }
+                    match get_nested_meta_ident(nm) {
+                        Some(id) if NeonMacrosAttrs::VALID_ARGS.iter().any(|s| id == s) => {
+                            parsed_attr.args.push(format!("{}", id));
+                        }
+                        _ => {
+                            return Err(syn::Error::new_spanned(
+                                nm,
+                                format!(
+                                    "Invalid neon_class arg '{}', expected one of {:?}",
+                                    quote! { #nm },
+                                    NeonMacrosAttrs::VALID_KEYS
+                                ),
+                            ));
+                        }
+                    }
+                }
+            }
+            Meta::NameValue(_) => {}
+        }
+    }
+
+    if neon_class_attribute_found {
+        Ok(Some(parsed_attr))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Checks if `attr` is a `neon_class` attribute, used as is or through its crate like
+/// `#[neon_class_macros::neon_class(method)]`.
+pub fn is_neon_class_attr(attr: &Attribute) -> bool {
+    attr.path
+        .segments
+        .last()
+        .map(|segment| segment.ident == "neon_class")
+        .unwrap_or(false)
+}
+
+/// Gets the value of a `js_name = "..."` arg.
+fn get_js_name(nm: &NestedMeta) -> syn::Result<Option<String>> {
+    if let NestedMeta::Meta(Meta::NameValue(nv)) = nm {
+        if nv.path.is_ident(NeonMacrosAttrs::JS_NAME) {
+            return match &nv.lit {
+                Lit::Str(s) => Ok(Some(s.value())),
+                lit => Err(syn::Error::new_spanned(
+                    lit,
+                    "Invalid value for 'js_name', expected a string like js_name = \"someName\"",
+                )),
+            };
+        }
+    }
+    Ok(None)
+}
+
+/// Gets the value of a `symbol = "..."` arg, the name of a well-known symbol like `iterator`.
+fn get_symbol(nm: &NestedMeta) -> syn::Result<Option<String>> {
+    if let NestedMeta::Meta(Meta::NameValue(nv)) = nm {
+        if nv.path.is_ident(NeonMacrosAttrs::SYMBOL) {
+            return match &nv.lit {
+                Lit::Str(s) if NeonMacrosAttrs::SYMBOLS.contains(&s.value().as_str()) => {
+                    Ok(Some(s.value()))
+                }
+                lit => Err(syn::Error::new_spanned(
+                    lit,
+                    format!(
+                        "Invalid value for 'symbol', expected one of {:?}",
+                        NeonMacrosAttrs::SYMBOLS
+                    ),
+                )),
+            };
+        }
+    }
+    Ok(None)
+}
+
+/// Finds the `js_name = "..."` arg in the args given to a macro.
+pub fn js_name_arg(attrs: &[NestedMeta]) -> syn::Result<Option<String>> {
+    for nm in attrs {
+        if let Some(js_name) = get_js_name(nm)? {
+            return Ok(Some(js_name));
+        }
+    }
+    Ok(None)
+}
+
+/// Naming convention used to go from a rust name to the JS side name.
+#[derive(Clone, Copy, PartialEq)]
+pub enum RenameRule {
+    /// Keep the rust name.
+    None,
+    SnakeCase,
+    /// `mixedCase`, the default for everything but constants.
+    CamelCase,
+    PascalCase,
+    ScreamingSnakeCase,
+}
+
+impl RenameRule {
+    /// The values `rename_all = "..."` takes, with their rule.
+    const VALUES: [(&'static str, RenameRule); 5] = [
+        ("none", RenameRule::None),
+        ("snake_case", RenameRule::SnakeCase),
+        ("camelCase", RenameRule::CamelCase),
+        ("PascalCase", RenameRule::PascalCase),
+        ("SCREAMING_SNAKE_CASE", RenameRule::ScreamingSnakeCase),
+    ];
+
+    fn from_value(value: &str) -> Option<RenameRule> {
+        Self::VALUES
+            .iter()
+            .find(|(name, _)| *name == value)
+            .map(|(_, rule)| *rule)
+    }
+
+    /// The value of `rename_all = "..."` that gives this rule.
+    pub fn value(self) -> &'static str {
+        Self::VALUES
+            .iter()
+            .find(|(_, rule)| *rule == self)
+            .map(|(name, _)| *name)
+            .unwrap()
+    }
+
+    pub fn apply(&self, name: &str) -> String {
+        match self {
+            RenameRule::None => name.to_string(),
+            RenameRule::SnakeCase => name.to_snake_case(),
+            RenameRule::CamelCase => name.to_mixed_case(),
+            // heck's `CamelCase` is what serde calls `PascalCase`
+            RenameRule::PascalCase => name.to_camel_case(),
+            RenameRule::ScreamingSnakeCase => name.to_shouty_snake_case(),
+        }
+    }
+}
+
+/// How the items of an `impl_block` get their JS name.
+#[derive(Clone, Copy)]
+pub enum JsNaming {
+    /// With the `rename_all` rule of the block itself.
+    Rule(Option<RenameRule>),
+    /// With the `rename_all` rule of the main `impl_block`, for a part that has none of its own.
+    /// The part expands on its own so it has the names for each rule, and picks one with the main
+    /// block's `__NEON_RENAME_ALL`.
+    Main,
+}
+
+impl JsNaming {
+    /// Tokens of the JS name of an item, given its name for a `rename_all` rule.
+    pub fn name_tok(self, js_name: impl Fn(Option<RenameRule>) -> String) -> TokenStream {
+        match self {
+            JsNaming::Rule(rename_all) => Literal::string(&js_name(rename_all)).into_token_stream(),
+            JsNaming::Main => {
+                let arms = RenameRule::VALUES.iter().map(|(value, rule)| {
+                    let name = Literal::string(&js_name(Some(*rule)));
+                    quote! { Some(#value) => #name }
+                });
+                let name = Literal::string(&js_name(None));
+                quote! {
+                    match Self::__NEON_RENAME_ALL {
+                        #(#arms,)*
+                        _ => #name,
+                    }
+                }
+            }
+        }
+    }
+
+    /// The rule to pair the items by their name with, i.e. a getter with its setter. The pairs are
+    /// the same with any rule.
+    pub fn pairing_rule(self) -> Option<RenameRule> {
+        match self {
+            JsNaming::Rule(rename_all) => rename_all,
+            JsNaming::Main => None,
+        }
+    }
+}
+
+/// Gets the value of a `rename_all = "..."` arg.
+fn get_rename_all(nm: &NestedMeta) -> syn::Result<Option<RenameRule>> {
+    if let NestedMeta::Meta(Meta::NameValue(nv)) = nm {
+        if nv.path.is_ident(ImplBlockArgs::RENAME_ALL) {
+            let rule = match &nv.lit {
+                Lit::Str(s) => RenameRule::from_value(&s.value()),
+                _ => None,
+            };
+            return match rule {
+                Some(rule) => Ok(Some(rule)),
+                None => Err(syn::Error::new_spanned(
+                    &nv.lit,
+                    format!(
+                        "Invalid value for 'rename_all', expected one of {:?}",
+                        RenameRule::VALUES.map(|(value, _)| value)
+                    ),
+                )),
+            };
+        }
+    }
+    Ok(None)
+}
+
+/// Finds the `rename_all = "..."` arg in the args given to a macro.
+pub fn rename_all_arg(attrs: &[NestedMeta]) -> syn::Result<Option<RenameRule>> {
+    for nm in attrs {
+        if let Some(rename_all) = get_rename_all(nm)? {
+            return Ok(Some(rename_all));
+        }
+    }
+    Ok(None)
+}
+
 pub struct NeonMacrosAttrs {
     /// The full method AST.
     pub method: ImplItemMethod,
@@ -227,113 +882,915 @@ pub struct NeonMacrosAttrs {
     /// For example, given `#[neon_class(method, throw_on_err)]` this `args` field would be:
     /// `["throw_on_err"]`
     pub args: Vec<String>,
+    /// Name given with `js_name = "..."`, if any.
+    ///
+    /// For example, given `#[neon_class(method, js_name = "toString")]` this `js_name` field would be:
+    /// `Some("toString")`
+    pub js_name: Option<String>,
+    /// Well-known symbol given with `symbol = "..."`, if any.
+    ///
+    /// For example, given `#[neon_class(method, symbol = "iterator")]` this `symbol` field would be:
+    /// `Some("iterator")`
+    pub symbol: Option<String>,
+}
+
+/// The key of a method on the JS prototype that isn't a string.
+pub enum SymbolKey {
+    /// A well-known symbol like `Symbol.iterator`.
+    WellKnown(String),
+    /// A symbol from the global registry like `Symbol.for('nodejs.util.inspect.custom')`.
+    Registered(&'static str),
 }
 
 impl NeonMacrosAttrs {
-    const VALID_ARGS: [&'static str; 1] = ["throw_on_err"];
+    const THROW_ON_ERR: &'static str = "throw_on_err";
+    const BLOCKING: &'static str = "blocking";
+    const VIRTUAL: &'static str = "virtual";
+    const JS_NAME: &'static str = "js_name";
+    const SYMBOL: &'static str = "symbol";
+    /// The args without a value.
+    const VALID_ARGS: [&'static str; 3] = [Self::THROW_ON_ERR, Self::BLOCKING, Self::VIRTUAL];
+    /// All the args, listed when an unknown one is found.
+    const VALID_KEYS: [&'static str; 5] = [
+        Self::THROW_ON_ERR,
+        Self::BLOCKING,
+        Self::VIRTUAL,
+        Self::JS_NAME,
+        Self::SYMBOL,
+    ];
+    /// The well-known symbols a method can be set on with `symbol = "..."`.
+    const SYMBOLS: [&'static str; 3] = ["iterator", "asyncIterator", "toPrimitive"];
 
-    pub fn new(method: ImplItemMethod) -> Option<Self> {
-        let mut parsed_attrs = NeonMacrosAttrs {
+    pub fn new(method: ImplItemMethod) -> syn::Result<Option<Self>> {
+        let attr = match parse_neon_class_attrs(&method.attrs)? {
+            Some(attr) => attr,
+            None => return Ok(None),
+        };
+        Ok(Some(NeonMacrosAttrs {
             method,
-            main: String::new(),
-            args: Vec::new(),
+            main: attr.main,
+            args: attr.args,
+            js_name: attr.js_name,
+            symbol: attr.symbol,
+        }))
+    }
+
+    /// Name of the method on the JS side.
+    ///
+    /// Unless given with `js_name = "..."`, this is the rust name with the `rename_all` rule applied
+    /// (`mixedCase` by default). For setters, the `set_` prefix is removed so they refer to the same
+    /// property as their getter.
+    pub fn js_name(&self, rename_all: Option<RenameRule>) -> String {
+        if let Some(js_name) = &self.js_name {
+            return js_name.clone();
+        }
+
+        // the async constructor is the class' factory, so it's named after what it does instead.
+        if self.is_async_constructor() {
+            return "create".to_string();
+        }
+
+        let name = format!("{}", self.method.sig.ident);
+        let name = if self.is_setter() {
+            name.strip_prefix("set_").unwrap_or(&name)
+        } else {
+            &name
         };
+        rename_all.unwrap_or(RenameRule::CamelCase).apply(name)
+    }
 
-        let mut neon_class_attribute_found = false;
+    pub fn is_constructor(&self) -> bool {
+        &self.main == "constructor"
+    }
 
-        parsed_attrs.method.attrs.iter().for_each(|attrs| {
-            if let Some(attribute_pkg) = attrs.path.segments.first() {
-                // TODO fix this for renames. currently, not sure how to get if the macro was renamed
-                // at import. We use the macro name here to find the methods in the ast that were marked as
-                // 'constructor' or 'method'. See the rename_macro_error.rs test.
-                if attribute_pkg.ident != "neon_class" {
-                    return;
-                } else {
-                    neon_class_attribute_found = true;
-                }
+    pub fn is_async_constructor(&self) -> bool {
+        &self.main == "async_constructor"
+    }
+
+    pub fn is_method(&self) -> bool {
+        &self.main == "method"
+    }
+
+    pub fn is_getter(&self) -> bool {
+        &self.main == "getter"
+    }
+
+    pub fn is_setter(&self) -> bool {
+        &self.main == "setter"
+    }
+
+    pub fn is_static_method(&self) -> bool {
+        &self.main == "static_method"
+    }
+
+    /// Checks if this is the hook used by node's `util.inspect`, i.e. `#[neon_class(inspect)]`.
+    pub fn is_inspect(&self) -> bool {
+        &self.main == "inspect"
+    }
+
+    /// The symbol the method is set on instead of its `js_name`, if any.
+    pub fn symbol_key(&self) -> Option<SymbolKey> {
+        if self.is_inspect() {
+            return Some(SymbolKey::Registered("nodejs.util.inspect.custom"));
+        }
+        self.symbol.clone().map(SymbolKey::WellKnown)
+    }
+
+    /// Checks if the method body runs on a worker thread, i.e. `#[neon_class(method, blocking)]`.
+    pub fn is_blocking(&self) -> bool {
+        self.args.iter().any(|arg| arg == Self::BLOCKING)
+    }
+
+    /// Checks if a JS subclass can override the method, i.e. `#[neon_class(method, virtual)]`.
+    pub fn is_virtual(&self) -> bool {
+        self.args.iter().any(|arg| arg == Self::VIRTUAL)
+    }
+
+    /// The args of a `virtual` method, which are serialized when calling a JS override.
+    ///
+    /// Panics if the method can't be called from rust with only serializable args and a
+    /// deserializable return value.
+    pub fn virtual_args(&self) -> Vec<(&Ident, &Type)> {
+        fn unsupported(ident: &Ident, reason: &str) -> ! {
+            panic!(
+                "The virtual method '{}' {}, virtual methods must take 'self' and args that can be serialized.",
+                ident, reason
+            )
+        }
+
+        let sig = &self.method.sig;
+        if sig.receiver().is_none() {
+            unsupported(&sig.ident, "doesn't take 'self'");
+        }
+        if sig.asyncness.is_some() || self.is_blocking() {
+            unsupported(&sig.ident, "runs after the JS call returns");
+        }
+        if let ReturnType::Type(_, ty) = &sig.output {
+            if matches!(ty.as_ref(), Type::Path(tp) if tp.path.segments.last().unwrap().ident == "JsResult")
+            {
+                unsupported(&sig.ident, "returns a 'JsResult'");
             }
+        }
 
-            let m = attrs.parse_meta().unwrap();
-            match &m {
-                Meta::Path(path) => {
-                    parsed_attrs
-                        .args
-                        .push(format!("{}", path.segments.last().unwrap().ident));
+        sig.inputs
+            .iter()
+            .filter_map(|fn_arg| match fn_arg {
+                FnArg::Typed(fn_arg) => Some(fn_arg),
+                FnArg::Receiver(_) => None,
+            })
+            .map(|fn_arg| {
+                let ident = match fn_arg.pat.as_ref() {
+                    Pat::Ident(p_ident) => &p_ident.ident,
+                    _ => unsupported(&sig.ident, "destructures an arg"),
+                };
+                if ident == "cx" || ident == "_cx" {
+                    unsupported(&sig.ident, "takes 'cx'");
                 }
-                Meta::List(meta_ls) => {
-                    let main_arg = meta_ls.nested.first().unwrap();
-                    match main_arg {
-                        NestedMeta::Meta(meta) => {
-                            if let Meta::Path(path) = meta {
-                                parsed_attrs.main =
-                                    format!("{}", path.segments.last().unwrap().ident);
-                            }
-                        }
-                        NestedMeta::Lit(_) => {}
+                if let Type::Path(tp) = fn_arg.ty.as_ref() {
+                    let ty = &tp.path.segments.last().unwrap().ident;
+                    if is_neon_handle(ty) || is_abort_token(tp) {
+                        unsupported(&sig.ident, &format!("takes a '{}'", ty));
                     }
-                    // Skip 1 here since the first one is saved as the main
-                    meta_ls.nested.iter().skip(1).for_each(|nm| {
-                        let id = get_nested_meta_ident(nm).unwrap();
-                        if Self::VALID_ARGS.iter().any(|s| id == s) {
-                            parsed_attrs.args.push(format!("{}", id));
-                        } else {
-                            panic!("Invalid arg {}", id);
+                }
+                (ident, fn_arg.ty.as_ref())
+            })
+            .collect()
+    }
+}
+
+/// How an instance of the decorated struct is stored inside its [`JsBox`](neon::prelude::JsBox).
+#[derive(Clone)]
+pub enum BoxedKind {
+    /// `JsBox<Self>`, methods can only take `&self`.
+    Plain,
+    /// `JsBox<RefCell<Self>>`, methods can take `&self` or `&mut self`.
+    RefCell,
+    /// `JsBox<Arc<Self>>`, methods can only take `&self` but `async` methods can keep `Self`
+    /// around after the JS call returns.
+    Shared,
+    /// `JsBox<Arc<neon_class_runtime::sync::Mutex<Self>>>`, methods can take `&self` or `&mut self`,
+    /// `async` ones included.
+    Mutex,
+}
+
+impl BoxedKind {
+    /// The type that ends up inside the [`JsBox`](neon::prelude::JsBox) to store an `inner`,
+    /// which is `Self` unless the `impl_block` is `remote`.
+    pub fn boxed_type(&self, inner: &TokenStream) -> TokenStream {
+        match self {
+            BoxedKind::Plain => quote! { #inner },
+            BoxedKind::RefCell => quote! { std::cell::RefCell<#inner> },
+            BoxedKind::Shared => quote! { std::sync::Arc<#inner> },
+            BoxedKind::Mutex => quote! { std::sync::Arc<neon_class_runtime::sync::Mutex<#inner>> },
+        }
+    }
+
+    /// Expression that turns `obj`, the stored type, into the boxed type.
+    pub fn wrap(&self, obj: &TokenStream) -> TokenStream {
+        match self {
+            BoxedKind::Plain => quote! { #obj },
+            BoxedKind::RefCell => quote! { std::cell::RefCell::new(#obj) },
+            BoxedKind::Shared => quote! { std::sync::Arc::new(#obj) },
+            BoxedKind::Mutex => {
+                quote! { std::sync::Arc::new(neon_class_runtime::sync::Mutex::new(#obj)) }
+            }
+        }
+    }
+
+    pub fn is_mutable(&self) -> bool {
+        matches!(self, BoxedKind::RefCell | BoxedKind::Mutex)
+    }
+
+    /// Checks if `Self` is behind an `Arc` so `async` methods can hold on to it.
+    pub fn is_shared(&self) -> bool {
+        matches!(self, BoxedKind::Shared | BoxedKind::Mutex)
+    }
+
+    /// The type borrowed for `'b` out of the boxed type by `__neon_borrow`, or by
+    /// `__neon_borrow_mut` if `mutable` is set.
+    pub fn guard_type(&self, mutable: bool) -> TokenStream {
+        match (self, mutable) {
+            (BoxedKind::Plain, _) | (BoxedKind::Shared, _) => quote! { &'b Self },
+            (BoxedKind::RefCell, false) => quote! { std::cell::Ref<'b, Self> },
+            (BoxedKind::RefCell, true) => quote! { std::cell::RefMut<'b, Self> },
+            (BoxedKind::Mutex, _) => quote! { neon_class_runtime::sync::MutexGuard<'b, Self> },
+        }
+    }
+}
+
+/// Args given to the `impl_block` macro.
+///
+/// For example, given `#[neon_class(impl_block, mutable = "Mutex")]` the `boxed` field would be
+/// [`BoxedKind::Mutex`].
+#[derive(Clone)]
+pub struct ImplBlockArgs {
+    pub boxed: BoxedKind,
+    /// Name of the exported class given with `js_name = "..."`, if any.
+    pub js_name: Option<String>,
+    /// Naming convention for the exported methods, accessors, static methods and constants.
+    pub rename_all: Option<RenameRule>,
+    /// The base class given with `extends = Base`, if any.
+    pub extends: Option<syn::Path>,
+    /// Set with `extendable`, allows other classes to extend this one.
+    pub extendable: bool,
+    /// Name given with `part = "..."`, if this block adds its items to the class of another one.
+    pub part: Option<String>,
+    /// Names of the parts given with `parts(...)` that add their items to this block's class.
+    pub parts: Vec<Ident>,
+    /// The concrete types of a generic struct given with `instantiate(...)`, with their JS name.
+    pub instantiate: Vec<(Type, String)>,
+    /// The foreign type given with `remote = some::Type`, stored in place of `Self`.
+    pub remote: Option<syn::Path>,
+    /// The rust traits given with `derive_js(...)` that are exported as JS methods.
+    pub derive_js: Vec<Ident>,
+    /// The `EventSender` field that backs the `on`, `once`, `off` and `removeAllListeners` of the
+    /// instances, `events` when given as a plain `events` or the one named with `events = "..."`.
+    pub events: Option<Ident>,
+}
+
+impl ImplBlockArgs {
+    const MUTABLE: &'static str = "mutable";
+    const JS_NAME: &'static str = "js_name";
+    const RENAME_ALL: &'static str = "rename_all";
+    const SHARED: &'static str = "shared";
+    const EXTENDS: &'static str = "extends";
+    const EXTENDABLE: &'static str = "extendable";
+    const PART: &'static str = "part";
+    const PARTS: &'static str = "parts";
+    const INSTANTIATE: &'static str = "instantiate";
+    const REMOTE: &'static str = "remote";
+    const DERIVE_JS: &'static str = "derive_js";
+    const EVENTS: &'static str = "events";
+    /// All the args, listed when an unknown one is found.
+    const VALID_ARGS: [&'static str; 12] = [
+        Self::MUTABLE,
+        Self::JS_NAME,
+        Self::RENAME_ALL,
+        Self::SHARED,
+        Self::EXTENDS,
+        Self::EXTENDABLE,
+        Self::PART,
+        Self::PARTS,
+        Self::INSTANTIATE,
+        Self::REMOTE,
+        Self::DERIVE_JS,
+        Self::EVENTS,
+    ];
+    /// The args a part can take, the others go on the main `impl_block`.
+    const PART_ARGS: [&'static str; 3] = [Self::PART, Self::RENAME_ALL, Self::MUTABLE];
+    /// The args that can't be combined with `remote`.
+    const NOT_REMOTE_ARGS: [&'static str; 6] = [
+        Self::EXTENDS,
+        Self::EXTENDABLE,
+        Self::PART,
+        Self::PARTS,
+        Self::INSTANTIATE,
+        Self::EVENTS,
+    ];
+    /// The traits `derive_js(...)` can export, see `derive_js_tok`.
+    pub const DERIVE_JS_TRAITS: [&'static str; 4] = ["Display", "Clone", "PartialEq", "Ord"];
+
+    /// Parses the args, an invalid one is an error spanned on that arg or its value.
+    pub fn new(args: &[NestedMeta]) -> syn::Result<Self> {
+        let mut parsed_args = ImplBlockArgs {
+            boxed: BoxedKind::Plain,
+            js_name: None,
+            rename_all: None,
+            extends: None,
+            extendable: false,
+            part: None,
+            parts: Vec::new(),
+            instantiate: Vec::new(),
+            remote: None,
+            derive_js: Vec::new(),
+            events: None,
+        };
+
+        // Skip 1 here since the first one is the `impl_block` itself.
+        for nm in args.iter().skip(1) {
+            match nm {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident(Self::JS_NAME) => {
+                    parsed_args.js_name = get_js_name(nm)?;
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident(Self::RENAME_ALL) => {
+                    parsed_args.rename_all = get_rename_all(nm)?;
+                }
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident(Self::MUTABLE) => {
+                    parsed_args.boxed = BoxedKind::RefCell;
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident(Self::MUTABLE) => {
+                    parsed_args.boxed = match &nv.lit {
+                        Lit::Str(s) if s.value() == "RefCell" => BoxedKind::RefCell,
+                        Lit::Str(s) if s.value() == "Mutex" => BoxedKind::Mutex,
+                        lit => {
+                            let message =
+                                "Invalid value for 'mutable', expected \"RefCell\" or \"Mutex\"";
+                            return Err(syn::Error::new_spanned(lit, message));
                         }
-                    });
+                    };
+                }
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident(Self::SHARED) => {
+                    parsed_args.boxed = BoxedKind::Shared;
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident(Self::EXTENDS) => {
+                    parsed_args.extends = Some(parse_lit(
+                        &nv.lit,
+                        "Invalid value for 'extends', expected a struct name",
+                    )?);
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident(Self::REMOTE) => {
+                    parsed_args.remote = Some(parse_lit(
+                        &nv.lit,
+                        "Invalid value for 'remote', expected a type",
+                    )?);
+                }
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident(Self::EXTENDABLE) => {
+                    parsed_args.extendable = true;
+                }
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident(Self::EVENTS) => {
+                    parsed_args.events = Some(format_ident!("{}", Self::EVENTS));
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident(Self::EVENTS) => {
+                    parsed_args.events = Some(parse_lit(
+                        &nv.lit,
+                        "Invalid value for 'events', expected a field name like events = \"emitter\"",
+                    )?);
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident(Self::PART) => {
+                    let part: Ident = parse_lit(
+                        &nv.lit,
+                        "Invalid value for 'part', expected a name like part = \"io\"",
+                    )?;
+                    parsed_args.part = Some(part.to_string());
+                }
+                NestedMeta::Meta(Meta::List(ls)) if ls.path.is_ident(Self::PARTS) => {
+                    parsed_args.parts = ls
+                        .nested
+                        .iter()
+                        .map(|nm| match get_nested_meta_ident(nm) {
+                            Some(id) => Ok(id.clone()),
+                            None => Err(syn::Error::new_spanned(
+                                nm,
+                                "Invalid value for 'parts', expected names like parts(io, stats)",
+                            )),
+                        })
+                        .collect::<syn::Result<_>>()?;
+                }
+                NestedMeta::Meta(Meta::List(ls)) if ls.path.is_ident(Self::DERIVE_JS) => {
+                    parsed_args.derive_js = ls
+                        .nested
+                        .iter()
+                        .map(|nm| match get_nested_meta_ident(nm) {
+                            Some(id) if Self::DERIVE_JS_TRAITS.iter().any(|t| id == t) => {
+                                Ok(id.clone())
+                            }
+                            _ => Err(syn::Error::new_spanned(
+                                nm,
+                                format!(
+                                    "Invalid value for 'derive_js', expected traits among {:?}",
+                                    Self::DERIVE_JS_TRAITS
+                                ),
+                            )),
+                        })
+                        .collect::<syn::Result<_>>()?;
+                }
+                // given as `instantiate(Decoder<Json> as "JsonDecoder")`, see `stringify_instantiate_args`.
+                NestedMeta::Meta(Meta::List(ls)) if ls.path.is_ident(Self::INSTANTIATE) => {
+                    parsed_args.instantiate = ls
+                        .nested
+                        .iter()
+                        .map(|nm| match nm {
+                            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.get_ident().is_some() => {
+                                let ty = parse_lit(&nv.lit, "Invalid type in 'instantiate'")?;
+                                Ok((ty, nv.path.get_ident().unwrap().to_string()))
+                            }
+                            _ => Err(syn::Error::new_spanned(
+                                nm,
+                                "Invalid value for 'instantiate', expected types like instantiate(Decoder<Json> as \"JsonDecoder\")",
+                            )),
+                        })
+                        .collect::<syn::Result<_>>()?;
+                }
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        nm,
+                        format!(
+                            "Invalid impl_block arg '{}', expected one of {:?}",
+                            quote! { #nm },
+                            Self::VALID_ARGS
+                        ),
+                    ))
                 }
-                Meta::NameValue(_) => {}
             }
-        });
+        }
 
-        if neon_class_attribute_found {
-            Some(parsed_attrs)
-        } else {
-            None
+        // the remote type isn't a struct of this crate, it can't take part in a class hierarchy
+        // nor have an `events` field.
+        if parsed_args.remote.is_some() {
+            if let Some(nm) = find_arg(args, &Self::NOT_REMOTE_ARGS) {
+                return Err(syn::Error::new_spanned(
+                    nm,
+                    format!(
+                        "'remote' can't be combined with {:?}.",
+                        Self::NOT_REMOTE_ARGS
+                    ),
+                ));
+            }
         }
+
+        // the classes of `instantiate` are named after its JS names.
+        if !parsed_args.instantiate.is_empty() {
+            if let Some(nm) = find_arg(args, &[Self::JS_NAME]) {
+                return Err(syn::Error::new_spanned(
+                    nm,
+                    "The classes of 'instantiate' are named by it, remove the 'js_name' arg.",
+                ));
+            }
+        }
+
+        // the class of a part is set up by the main `impl_block`, a part's `mutable` only lets it
+        // have `&mut self` methods.
+        if let Some(part) = &parsed_args.part {
+            let other_arg = args
+                .iter()
+                .skip(1)
+                .find(|nm| !has_arg_name(nm, &Self::PART_ARGS));
+            if let Some(nm) = other_arg {
+                return Err(syn::Error::new_spanned(
+                    nm,
+                    format!(
+                        "The part '{}' only takes 'rename_all' and 'mutable', the other args go on the main 'impl_block'.",
+                        part
+                    ),
+                ));
+            }
+        }
+
+        Ok(parsed_args)
     }
+}
 
-    pub fn is_constructor(&self) -> bool {
-        &self.main == "constructor"
+/// Parses the string literal `lit` as a `T`, `message` is the error when it's not one.
+fn parse_lit<T: syn::parse::Parse>(lit: &Lit, message: &str) -> syn::Result<T> {
+    match lit {
+        Lit::Str(s) => s
+            .parse()
+            .map_err(|e| syn::Error::new_spanned(lit, format!("{}: {}", message, e))),
+        _ => Err(syn::Error::new_spanned(lit, message)),
     }
+}
 
-    pub fn is_method(&self) -> bool {
-        &self.main == "method"
+/// Checks if the arg `nm` is named one of `names`, like `mutable` in `mutable = "Mutex"`.
+fn has_arg_name(nm: &NestedMeta, names: &[&str]) -> bool {
+    match nm {
+        NestedMeta::Meta(meta) => names.iter().any(|name| meta.path().is_ident(name)),
+        NestedMeta::Lit(_) => false,
     }
 }
 
+/// Finds the first of `args` named one of `names`.
+fn find_arg<'a>(args: &'a [NestedMeta], names: &[&str]) -> Option<&'a NestedMeta> {
+    args.iter().find(|nm| has_arg_name(nm, names))
+}
+
+/// Turns the `name = some::Path` args listed in `names` into `name = "some::Path"`.
+///
+/// [`AttributeArgs`](syn::AttributeArgs) only takes literals after the `=`, this lets args like
+/// `extends = Base` be written without quotes.
+pub fn stringify_path_args(args: TokenStream, names: &[&str]) -> TokenStream {
+    let mut tokens = args.into_iter().peekable();
+    let mut stringified = TokenStream::new();
+    while let Some(tt) = tokens.next() {
+        let is_path_arg = matches!(&tt, TokenTree::Ident(id) if names.iter().any(|n| id == n));
+        stringified.extend([tt]);
+        if !is_path_arg {
+            continue;
+        }
+        match tokens.peek() {
+            Some(TokenTree::Punct(p)) if p.as_char() == '=' => stringified.extend(tokens.next()),
+            _ => continue,
+        }
+
+        // the path ends at the next comma that isn't part of generic args.
+        let mut path = TokenStream::new();
+        let mut depth = 0;
+        while let Some(tt) = tokens.peek() {
+            match tt {
+                TokenTree::Punct(p) if p.as_char() == ',' && depth == 0 => break,
+                TokenTree::Punct(p) if p.as_char() == '<' => depth += 1,
+                TokenTree::Punct(p) if p.as_char() == '>' => depth -= 1,
+                _ => {}
+            }
+            path.extend(tokens.next());
+        }
+        let mut path_tokens = path.clone().into_iter();
+        match (path_tokens.next(), path_tokens.next()) {
+            (Some(TokenTree::Literal(_)), None) => stringified.extend(path),
+            _ => stringified.extend([TokenTree::Literal(Literal::string(&path.to_string()))]),
+        }
+    }
+    stringified
+}
+
+/// Turns `instantiate(Decoder<Json> as "JsonDecoder", ...)` into
+/// `instantiate(JsonDecoder = "Decoder<Json>", ...)` so it can be parsed as
+/// [`AttributeArgs`](syn::AttributeArgs).
+pub fn stringify_instantiate_args(args: TokenStream) -> syn::Result<TokenStream> {
+    let mut tokens = args.into_iter().peekable();
+    let mut stringified = TokenStream::new();
+    while let Some(tt) = tokens.next() {
+        let is_instantiate =
+            matches!(&tt, TokenTree::Ident(id) if id == ImplBlockArgs::INSTANTIATE);
+        stringified.extend([tt]);
+        let group = match tokens.peek() {
+            Some(TokenTree::Group(group)) if is_instantiate => group.clone(),
+            _ => continue,
+        };
+        tokens.next();
+
+        // each entry ends at the next comma that isn't part of generic args.
+        let mut entries = vec![Vec::new()];
+        let mut depth = 0;
+        for tt in group.stream() {
+            match &tt {
+                TokenTree::Punct(p) if p.as_char() == ',' && depth == 0 => {
+                    entries.push(Vec::new());
+                    continue;
+                }
+                TokenTree::Punct(p) if p.as_char() == '<' => depth += 1,
+                TokenTree::Punct(p) if p.as_char() == '>' => depth -= 1,
+                _ => {}
+            }
+            entries.last_mut().unwrap().push(tt);
+        }
+
+        let entries = entries
+            .into_iter()
+            .filter(|e| !e.is_empty())
+            .map(|entry| {
+                let (js_name, ty) = match entry.split_last() {
+                    Some((TokenTree::Literal(js_name), [ty @ .., TokenTree::Ident(as_kw)]))
+                        if as_kw == "as" && !ty.is_empty() =>
+                    {
+                        (js_name, ty)
+                    }
+                    _ => {
+                        return Err(syn::Error::new_spanned(
+                            entry.iter().cloned().collect::<TokenStream>(),
+                            "Invalid value for 'instantiate', expected types like instantiate(Decoder<Json> as \"JsonDecoder\")",
+                        ))
+                    }
+                };
+                let js_name = syn::parse_str::<syn::LitStr>(&js_name.to_string())
+                    .and_then(|lit| lit.parse::<Ident>())
+                    .map_err(|_| {
+                        syn::Error::new(
+                            js_name.span(),
+                            format!(
+                                "The JS name {} in 'instantiate' must be a string with a valid identifier",
+                                js_name
+                            ),
+                        )
+                    })?;
+                let ty = Literal::string(&ty.iter().cloned().collect::<TokenStream>().to_string());
+                Ok(quote! { #js_name = #ty })
+            })
+            .collect::<syn::Result<Vec<_>>>()?;
+        let group = proc_macro2::Group::new(group.delimiter(), quote! { #(#entries),* });
+        stringified.extend([TokenTree::Group(group)]);
+    }
+    Ok(stringified)
+}
+
+/// Replaces every ident of `substitutions` in `tokens` with its tokens.
+pub fn substitute_idents(
+    tokens: TokenStream,
+    substitutions: &[(Ident, TokenStream)],
+) -> TokenStream {
+    tokens
+        .into_iter()
+        .flat_map(|tt| match tt {
+            TokenTree::Ident(id) => match substitutions.iter().find(|(param, _)| *param == id) {
+                Some((_, arg)) => quote! { #arg },
+                None => quote! { #id },
+            },
+            TokenTree::Group(group) => {
+                let mut substituted = proc_macro2::Group::new(
+                    group.delimiter(),
+                    substitute_idents(group.stream(), substitutions),
+                );
+                substituted.set_span(group.span());
+                quote! { #substituted }
+            }
+            tt => quote! { #tt },
+        })
+        .collect()
+}
+
+/// Replaces the type params of the generic `impl_ast` with the args of the concrete type `ty`.
+///
+/// For `impl<T: Codec> Decoder<T>` and `Decoder<Json>` this gives `impl Decoder<Json>` with every
+/// `T` in its items replaced by `Json`.
+pub fn instantiate_impl(impl_ast: &ItemImpl, ty: &Type) -> ItemImpl {
+    let generic_args = |ty: &Type| -> Vec<TokenStream> {
+        match ty {
+            Type::Path(tp) => match &tp.path.segments.last().unwrap().arguments {
+                PathArguments::AngleBracketed(args) => {
+                    args.args.iter().map(|arg| quote! { #arg }).collect()
+                }
+                _ => Vec::new(),
+            },
+            _ => panic!(
+                "Only structs can be instantiated, found '{}'",
+                quote! { #ty }
+            ),
+        }
+    };
+    let params = generic_args(&impl_ast.self_ty);
+    let args = generic_args(ty);
+    if params.len() != args.len() {
+        panic!(
+            "'{}' doesn't have the same generic args as '{}'",
+            quote! { #ty },
+            impl_ast.self_ty.to_token_stream()
+        );
+    }
+
+    let substitutions: Vec<(Ident, TokenStream)> = params
+        .into_iter()
+        .zip(args)
+        .filter_map(|(param, arg)| {
+            let param = syn::parse2::<Ident>(param).ok()?;
+            let is_type_param = impl_ast.generics.type_params().any(|tp| tp.ident == param);
+            if is_type_param {
+                Some((param, arg))
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    let items = impl_ast
+        .items
+        .iter()
+        .map(|item| {
+            syn::parse2::<ImplItem>(substitute_idents(quote! { #item }, &substitutions))
+                .expect("Substituting the generic params should give valid impl items")
+        })
+        .collect();
+
+    ItemImpl {
+        generics: Default::default(),
+        self_ty: Box::new(ty.clone()),
+        items,
+        ..impl_ast.clone()
+    }
+}
+
+/// Checks if the receiver of a method is `&mut self`.
+pub fn takes_mut_self(inputs: &Punctuated<FnArg, Comma>) -> bool {
+    inputs.iter().any(|arg| {
+        if let FnArg::Receiver(r) = arg {
+            r.reference.is_some() && r.mutability.is_some()
+        } else {
+            false
+        }
+    })
+}
+
 pub struct ImplTree {
     /// Only allow one constructor since only one value can be exported with a given struct's name.
-    pub constructor: Option<ImplItemMethod>,
-    pub methods: Vec<ImplItemMethod>,
+    pub constructor: Option<NeonMacrosAttrs>,
+    pub methods: Vec<NeonMacrosAttrs>,
+    pub getters: Vec<NeonMacrosAttrs>,
+    pub setters: Vec<NeonMacrosAttrs>,
+    /// Associated functions exported on the JS constructor.
+    pub static_methods: Vec<NeonMacrosAttrs>,
+    /// `async` factory exported on the JS constructor, i.e. `TestStruct.create(...)`.
+    pub async_constructor: Option<NeonMacrosAttrs>,
+}
+
+/// An accessor property on the JS prototype, made from a getter, a setter or both.
+pub struct Accessor<'a> {
+    /// Name of the property on the JS side.
+    pub js_name: String,
+    /// The getter, or the setter if there's no getter, the property is named after.
+    pub named_by: &'a NeonMacrosAttrs,
+    pub getter: Option<&'a ImplItemMethod>,
+    pub setter: Option<&'a ImplItemMethod>,
 }
 
 impl ImplTree {
     pub fn new(methods: Vec<NeonMacrosAttrs>) -> Self {
         let mut s = ImplTree {
             constructor: None,
-            methods: Vec::with_capacity(methods.len() - 1),
+            methods: Vec::with_capacity(methods.len()),
+            getters: Vec::new(),
+            setters: Vec::new(),
+            static_methods: Vec::new(),
+            async_constructor: None,
         };
 
         for method in methods {
+            if method.method.sig.asyncness.is_some()
+                && (method.is_constructor()
+                    || method.is_getter()
+                    || method.is_setter()
+                    || method.is_inspect())
+            {
+                panic!(
+                    "'{}' can't be async, only methods, static methods and functions can.",
+                    method.method.sig.ident
+                )
+            }
+
+            if method.is_blocking() && !(method.is_method() || method.is_static_method()) {
+                panic!(
+                    "'{}' can't be blocking, only methods, static methods and functions can.",
+                    method.method.sig.ident
+                )
+            }
+
+            if method.is_virtual() {
+                if !method.is_method() {
+                    panic!(
+                        "'{}' can't be virtual, only methods can.",
+                        method.method.sig.ident
+                    )
+                }
+                method.virtual_args();
+            }
+
+            if method.symbol.is_some() {
+                if !method.is_method() {
+                    panic!(
+                        "'{}' can't be set on a symbol, only methods can.",
+                        method.method.sig.ident
+                    )
+                }
+                if method.js_name.is_some() || method.is_virtual() {
+                    panic!(
+                        "The method '{}' is set on a symbol, it can't also take 'js_name' or be 'virtual'.",
+                        method.method.sig.ident
+                    )
+                }
+            }
+
             if method.is_constructor() {
                 if s.constructor.is_none() {
-                    s.constructor = Some(method.method);
+                    s.constructor = Some(method);
                 } else {
                     panic!(
                         "There is already a method annotated as constructor with the name '{}'.\n\
                     With neon we can only export one value so the method '{}' is not allowed.\n\
                     To fix it, choose one of the two.",
-                        s.constructor.unwrap().sig.ident,
+                        s.constructor.unwrap().method.sig.ident,
                         method.method.sig.ident
                     )
                 }
             } else if method.is_method() {
-                s.methods.push(method.method);
+                s.methods.push(method);
+            } else if method.is_inspect() {
+                if method.method.sig.receiver().is_none() {
+                    panic!(
+                        "The inspect hook '{}' must take 'self'.",
+                        method.method.sig.ident
+                    )
+                }
+                if let Some(inspect) = s.methods.iter().find(|m| m.is_inspect()) {
+                    panic!(
+                        "There is already a method annotated as inspect with the name '{}'.\n\
+                    To fix it, choose one of the two.",
+                        inspect.method.sig.ident
+                    )
+                }
+                s.methods.push(method);
+            } else if method.is_getter() {
+                s.getters.push(method);
+            } else if method.is_setter() {
+                let args = method.method.sig.inputs.len();
+                if args != 2 {
+                    panic!(
+                        "The setter '{}' must take '&mut self' (or '&self') and exactly one value but it takes {} args.",
+                        method.method.sig.ident, args
+                    )
+                }
+                s.setters.push(method);
+            } else if method.is_async_constructor() {
+                let sig = &method.method.sig;
+                let returns_self = match &sig.output {
+                    ReturnType::Type(_, ty) => returns_self(ty).is_some(),
+                    ReturnType::Default => false,
+                };
+                if sig.asyncness.is_none() || sig.receiver().is_some() || !returns_self {
+                    panic!(
+                        "The async constructor '{}' must be an 'async fn' that doesn't take 'self' and returns 'Self' or 'Result<Self, E>'.",
+                        sig.ident
+                    )
+                }
+                if let Some(async_constructor) = s.async_constructor {
+                    panic!(
+                        "There is already a method annotated as async_constructor with the name '{}'.\n\
+                    To fix it, choose one of the two.",
+                        async_constructor.method.sig.ident
+                    )
+                }
+                s.async_constructor = Some(method);
+            } else if method.is_static_method() {
+                if method.method.sig.receiver().is_some() {
+                    panic!(
+                        "The static method '{}' can't take 'self'. To fix it, remove 'self' or use '#[neon_class(method)]' instead.",
+                        method.method.sig.ident
+                    )
+                }
+                s.static_methods.push(method);
             }
         }
 
         s
     }
+
+    /// Every decorated method that gets called with a JS `this`.
+    pub fn exported_methods(&self) -> impl Iterator<Item = &ImplItemMethod> {
+        self.methods
+            .iter()
+            .chain(self.getters.iter())
+            .chain(self.setters.iter())
+            .map(|m| &m.method)
+    }
+
+    /// Pairs the getters and setters that refer to the same JS property.
+    ///
+    /// A getter `fn size(&self)` and a setter `fn set_size(&mut self, size: u32)` both refer to the
+    /// `size` property.
+    pub fn accessors(&self, rename_all: Option<RenameRule>) -> Vec<Accessor<'_>> {
+        let mut accessors: Vec<Accessor> = self
+            .getters
+            .iter()
+            .map(|getter| Accessor {
+                js_name: getter.js_name(rename_all),
+                named_by: getter,
+                getter: Some(&getter.method),
+                setter: None,
+            })
+            .collect();
+
+        for setter in &self.setters {
+            let js_name = setter.js_name(rename_all);
+            if let Some(accessor) = accessors.iter_mut().find(|a| a.js_name == js_name) {
+                accessor.setter = Some(&setter.method);
+            } else {
+                accessors.push(Accessor {
+                    js_name,
+                    named_by: setter,
+                    getter: None,
+                    setter: Some(&setter.method),
+                });
+            }
+        }
+
+        accessors
+    }
 }
 
 pub fn get_lifetime_from_return_type(output: &ReturnType) -> Option<Lifetime> {
@@ -361,7 +1818,16 @@ fn get_nested_meta_ident(nm: &NestedMeta) -> Option<&Ident> {
 
 pub fn throws_on_err(attrs: &[NestedMeta]) -> bool {
     attrs.iter().any(|attr| {
-        let id = get_nested_meta_ident(attr).unwrap();
-        id == NeonMacrosAttrs::VALID_ARGS[0]
+        get_nested_meta_ident(attr)
+            .map(|id| id == NeonMacrosAttrs::THROW_ON_ERR)
+            .unwrap_or(false)
+    })
+}
+
+pub fn is_blocking(attrs: &[NestedMeta]) -> bool {
+    attrs.iter().any(|attr| {
+        get_nested_meta_ident(attr)
+            .map(|id| id == NeonMacrosAttrs::BLOCKING)
+            .unwrap_or(false)
     })
 }